@@ -0,0 +1,105 @@
+// Compares `MyVec`'s growth policies: how fast pushing is, how much memory the buffer peaks at,
+// and how many times it has to reallocate on the way.
+//
+// `cargo bench --bench growth`
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use wk9_01_implementing_vec::{
+    CountingAllocator, Doubling, FixedIncrement, Global, GrowthPolicy, MyVec, OneAndAHalf,
+    PageAligned,
+};
+
+// each run pushes this many bytes' worth of elements, whatever their size
+const TOTAL_BYTES: usize = 1 << 20;
+
+struct Row {
+    ns_per_push: f64,
+    peak_bytes: usize,
+    reallocations: usize,
+}
+
+fn measure<T: Copy, G: GrowthPolicy>(value: T) -> Row {
+    let count = TOTAL_BYTES / std::mem::size_of::<T>();
+
+    // best of a few runs, to cut down on noise
+    let elapsed = (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            let mut vec = MyVec::<T, Global, G>::with_growth_in(Global);
+            for _ in 0..count {
+                vec.push(black_box(value));
+            }
+            black_box(&vec);
+            start.elapsed()
+        })
+        .min()
+        .unwrap();
+
+    // and once more through a `CountingAllocator` to see what happened to the buffer
+    let counter = CountingAllocator::new();
+    let mut vec = MyVec::<T, _, G>::with_growth_in(&counter);
+    for _ in 0..count {
+        vec.push(value);
+    }
+    drop(vec);
+
+    Row {
+        ns_per_push: elapsed.as_nanos() as f64 / count as f64,
+        peak_bytes: counter.peak_bytes_in_use(),
+        reallocations: counter.reallocations(),
+    }
+}
+
+fn print_rows<T: Copy>(value: T) {
+    let rows = [
+        ("Doubling", measure::<T, Doubling>(value)),
+        ("OneAndAHalf", measure::<T, OneAndAHalf>(value)),
+        (
+            "FixedIncrement<1024>",
+            measure::<T, FixedIncrement<1024>>(value),
+        ),
+        ("PageAligned", measure::<T, PageAligned>(value)),
+    ];
+
+    let size = std::mem::size_of::<T>();
+    let needed = TOTAL_BYTES / size * size;
+    for (name, row) in rows {
+        println!(
+            "{:>6} | {:<20} | {:>10.2} ns | {:>9} KiB | {:>7.1}% | {:>8}",
+            size,
+            name,
+            row.ns_per_push,
+            row.peak_bytes / 1024,
+            // how much bigger the buffer got than it needed to be
+            (row.peak_bytes as f64 / needed as f64 - 1.0) * 100.0,
+            row.reallocations,
+        );
+    }
+    println!(
+        "{:-<7}+{:-<22}+{:-<15}+{:-<15}+{:-<10}+{:-<9}",
+        "", "", "", "", "", ""
+    );
+}
+
+fn main() {
+    println!(
+        "push {} KiB worth of elements into a MyVec with each growth policy",
+        TOTAL_BYTES / 1024
+    );
+    println!();
+    println!(
+        "{:>6} | {:<20} | {:>13} | {:>13} | {:>8} | {:>8}",
+        "size", "policy", "per push", "peak memory", "waste", "reallocs"
+    );
+    println!(
+        "{:-<7}+{:-<22}+{:-<15}+{:-<15}+{:-<10}+{:-<9}",
+        "", "", "", "", "", ""
+    );
+
+    print_rows(1u8);
+    print_rows(1u64);
+    print_rows([1u64; 8]);
+    print_rows([1u8; 500]);
+}
@@ -0,0 +1,104 @@
+// Counts words with `MyHashMap` (at a few load factors) and `std::collections::HashMap`,
+// using `entry().and_modify().or_insert()` like `wk2_00_collections` does.
+//
+// `cargo bench --bench hash_map`
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use wk9_01_implementing_vec::MyHashMap;
+
+const WORDS: usize = 1_000_000;
+const VOCABULARY: usize = 20_000;
+
+/// A made-up text where a few words are very common and most are rare, like real text.
+fn text() -> String {
+    // a cheap xorshift, so the text is the same every run
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    let mut text = String::new();
+    for _ in 0..WORDS {
+        // multiplying two uniform picks skews towards small word numbers
+        let word = (next() % VOCABULARY as u64) * (next() % VOCABULARY as u64) / VOCABULARY as u64;
+        text.push_str(&format!("w{word:x} "));
+    }
+    text
+}
+
+/// Best of a few runs, to cut down on noise.
+fn time(mut f: impl FnMut() -> usize) -> (Duration, usize) {
+    let mut distinct = 0;
+    let best = (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            distinct = black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap();
+    (best, distinct)
+}
+
+fn my_hash_map(text: &str, max_load_factor: f64) -> (Duration, usize) {
+    time(|| {
+        let mut counts = MyHashMap::new().with_max_load_factor(max_load_factor);
+        for word in text.split_ascii_whitespace() {
+            counts
+                .entry(word)
+                .and_modify(|count| *count += 1)
+                .or_insert(1);
+        }
+        counts.len()
+    })
+}
+
+fn std_hash_map(text: &str) -> (Duration, usize) {
+    time(|| {
+        let mut counts = HashMap::new();
+        for word in text.split_ascii_whitespace() {
+            counts
+                .entry(word)
+                .and_modify(|count| *count += 1)
+                .or_insert(1);
+        }
+        counts.len()
+    })
+}
+
+fn main() {
+    let text = text();
+
+    println!("count {WORDS} words (from a vocabulary of about {VOCABULARY})");
+    println!();
+    println!(
+        "{:<26} | {:>10} | {:>10} | {:>8}",
+        "map", "total", "per word", "distinct"
+    );
+    println!("{:-<27}+{:-<12}+{:-<12}+{:-<9}", "", "", "", "");
+
+    let mut rows = vec![];
+    for max_load_factor in [0.5, 0.75, 0.875, 0.95] {
+        rows.push((
+            format!("MyHashMap (load {max_load_factor})"),
+            my_hash_map(&text, max_load_factor),
+        ));
+    }
+    rows.push(("std HashMap".to_string(), std_hash_map(&text)));
+
+    for (name, (elapsed, distinct)) in rows {
+        println!(
+            "{:<26} | {:>7.2} ms | {:>7.1} ns | {:>8}",
+            name,
+            elapsed.as_secs_f64() * 1000.0,
+            elapsed.as_nanos() as f64 / WORDS as f64,
+            distinct,
+        );
+    }
+}
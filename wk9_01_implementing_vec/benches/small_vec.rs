@@ -0,0 +1,82 @@
+// Compares building lots of small vectors with `MySmallVec`, `MyVec` and `Vec`.
+//
+// `cargo bench --bench small_vec`
+//
+// There's no `#[bench]` on stable Rust, so this is just a binary that times things with `Instant`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use wk9_01_implementing_vec::{MySmallVec, MyVec};
+
+const ROUNDS: usize = 200_000;
+
+/// Times `f` over `ROUNDS` rounds, taking the best of a few runs to cut down on noise.
+fn time(mut f: impl FnMut(usize) -> u64) -> Duration {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            let mut total = 0u64;
+            for round in 0..ROUNDS {
+                total = total.wrapping_add(f(black_box(round)));
+            }
+            black_box(total);
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn small_vec(len: usize) -> Duration {
+    time(|round| {
+        let mut vec = MySmallVec::<u64, 8>::new();
+        for i in 0..len {
+            vec.push((round + i) as u64);
+        }
+        vec.iter().sum()
+    })
+}
+
+fn my_vec(len: usize) -> Duration {
+    time(|round| {
+        let mut vec = MyVec::new();
+        for i in 0..len {
+            vec.push((round + i) as u64);
+        }
+        vec.iter().sum()
+    })
+}
+
+fn std_vec(len: usize) -> Duration {
+    time(|round| {
+        let mut vec = Vec::new();
+        for i in 0..len {
+            vec.push((round + i) as u64);
+        }
+        vec.iter().sum()
+    })
+}
+
+fn per_round(duration: Duration) -> String {
+    format!("{:.1} ns", duration.as_nanos() as f64 / ROUNDS as f64)
+}
+
+fn main() {
+    println!("build, sum and drop a vector of u64s ({ROUNDS} rounds, MySmallVec inline size 8)");
+    println!();
+    println!(
+        "{:>6} | {:>14} | {:>14} | {:>14}",
+        "len", "MySmallVec", "MyVec", "Vec"
+    );
+    println!("{:-<7}+{:-<16}+{:-<16}+{:-<15}", "", "", "", "");
+
+    for len in [1, 2, 4, 8, 9, 16, 64] {
+        println!(
+            "{:>6} | {:>14} | {:>14} | {:>14}",
+            len,
+            per_round(small_vec(len)),
+            per_round(my_vec(len)),
+            per_round(std_vec(len)),
+        );
+    }
+}
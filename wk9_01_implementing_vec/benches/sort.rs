@@ -0,0 +1,73 @@
+// Times `MyVec`'s own sorts against the slice ones from std, on a few input shapes.
+//
+// `cargo bench --bench sort`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use wk9_01_implementing_vec::MyVec;
+
+const LEN: usize = 200_000;
+
+fn inputs() -> Vec<(&'static str, Vec<u64>)> {
+    // a cheap xorshift, so the inputs are the same every run
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    let n = LEN as u64;
+    vec![
+        ("sorted", (0..n).collect()),
+        ("reversed", (0..n).rev().collect()),
+        ("random", (0..n).map(|_| next()).collect()),
+        ("many duplicates", (0..n).map(|_| next() % 16).collect()),
+    ]
+}
+
+/// Best of a few runs of sorting a fresh copy of `input`, to cut down on noise.
+fn time(input: &[u64], mut sort: impl FnMut(&mut MyVec<u64>)) -> Duration {
+    (0..5)
+        .map(|_| {
+            let mut vec = MyVec::from(input.to_vec());
+            let start = Instant::now();
+            sort(black_box(&mut vec));
+            let elapsed = start.elapsed();
+            assert!(vec.windows(2).all(|pair| pair[0] <= pair[1]));
+            elapsed
+        })
+        .min()
+        .unwrap()
+}
+
+fn ms(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn main() {
+    println!("sort {LEN} u64s");
+    println!();
+    println!(
+        "{:<16} | {:>10} | {:>10} | {:>14} | {:>14}",
+        "input", "sort", "std sort", "sort_unstable", "std unstable"
+    );
+    println!(
+        "{:-<17}+{:-<12}+{:-<12}+{:-<16}+{:-<15}",
+        "", "", "", "", ""
+    );
+
+    for (shape, input) in inputs() {
+        println!(
+            "{:<16} | {:>10} | {:>10} | {:>14} | {:>14}",
+            shape,
+            ms(time(&input, |vec| vec.sort())),
+            // going through the slice gets std's version instead of ours
+            ms(time(&input, |vec| vec.as_mut_slice().sort())),
+            ms(time(&input, |vec| vec.sort_unstable())),
+            ms(time(&input, |vec| vec.as_mut_slice().sort_unstable())),
+        );
+    }
+}
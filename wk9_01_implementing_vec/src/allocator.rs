@@ -0,0 +1,483 @@
+//! The allocator interface [`MyVec`](crate::MyVec) is generic over, and a few allocators to plug into it.
+//!
+//! `std::alloc::Allocator` is still nightly-only, so we have our own (much smaller) version of it.
+
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::ptr::NonNull;
+
+/// Something that can hand out and take back blocks of memory.
+///
+/// # Safety
+///
+/// Implementors must make sure that:
+/// - a block returned by `allocate` is valid for reads and writes of `layout.size()` bytes,
+///   is aligned to `layout.align()`, and stays valid until it's passed to `deallocate`
+///   (or a successful `reallocate`), even if the allocator itself is moved
+/// - blocks never overlap while they're both alive
+pub unsafe trait MyAllocator {
+    /// Allocates a block for `layout`, or returns `None` if that's not possible.
+    ///
+    /// Callers never ask for zero-sized blocks.
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>>;
+
+    /// Gives a block back to the allocator.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by this allocator with exactly `layout`, and not freed since.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+    /// Moves a block to one that fits `new_layout`, keeping the first
+    /// `min(old_layout.size(), new_layout.size())` bytes.
+    ///
+    /// On failure `None` is returned and the old block is left untouched. By default this
+    /// allocates a new block and copies, but allocators can override it to grow in place.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`MyAllocator::deallocate`] for `ptr` and `old_layout`, and `new_layout`
+    /// must have the same alignment and a non-zero size.
+    unsafe fn reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        let new_ptr = self.allocate(new_layout)?;
+
+        // SAFETY: both blocks are at least this big, and they can't overlap since the old one is still alive
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                ptr.as_ptr(),
+                new_ptr.as_ptr(),
+                old_layout.size().min(new_layout.size()),
+            );
+            self.deallocate(ptr, old_layout);
+        }
+
+        Some(new_ptr)
+    }
+}
+
+// lets a collection borrow an allocator, so the same one can back several collections
+// (and you can still look at it afterwards)
+unsafe impl<A: MyAllocator + ?Sized> MyAllocator for &A {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        (**self).allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { (**self).deallocate(ptr, layout) }
+    }
+
+    unsafe fn reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        unsafe { (**self).reallocate(ptr, old_layout, new_layout) }
+    }
+}
+
+/// The global allocator, i.e. whatever `std::alloc::alloc` uses. This is the default for [`MyVec`](crate::MyVec).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Global;
+
+unsafe impl MyAllocator for Global {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        // SAFETY: callers never ask for zero-sized blocks
+        NonNull::new(unsafe { alloc::alloc::alloc(layout) })
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // SAFETY: the caller promises `ptr` came from us with this layout
+        unsafe { alloc::alloc::dealloc(ptr.as_ptr(), layout) }
+    }
+
+    unsafe fn reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        // SAFETY: the caller promises `ptr` came from us with `old_layout`, and that the new size is non-zero
+        NonNull::new(unsafe { alloc::alloc::realloc(ptr.as_ptr(), old_layout, new_layout.size()) })
+    }
+}
+
+/// Wraps another allocator and keeps count of what goes through it.
+#[derive(Debug, Default)]
+pub struct CountingAllocator<A: MyAllocator = Global> {
+    inner: A,
+    allocations: Cell<usize>,
+    deallocations: Cell<usize>,
+    reallocations: Cell<usize>,
+    bytes_in_use: Cell<usize>,
+    peak_bytes_in_use: Cell<usize>,
+}
+
+impl CountingAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<A: MyAllocator> CountingAllocator<A> {
+    pub fn wrapping(inner: A) -> Self {
+        Self {
+            inner,
+            allocations: Cell::new(0),
+            deallocations: Cell::new(0),
+            reallocations: Cell::new(0),
+            bytes_in_use: Cell::new(0),
+            peak_bytes_in_use: Cell::new(0),
+        }
+    }
+
+    /// How many blocks have been allocated (reallocations don't count).
+    pub fn allocations(&self) -> usize {
+        self.allocations.get()
+    }
+
+    /// How many blocks have been given back.
+    pub fn deallocations(&self) -> usize {
+        self.deallocations.get()
+    }
+
+    pub fn reallocations(&self) -> usize {
+        self.reallocations.get()
+    }
+
+    /// How many blocks are allocated and haven't been given back yet.
+    pub fn live_allocations(&self) -> usize {
+        self.allocations() - self.deallocations()
+    }
+
+    pub fn bytes_in_use(&self) -> usize {
+        self.bytes_in_use.get()
+    }
+
+    /// The most `bytes_in_use` has ever been.
+    pub fn peak_bytes_in_use(&self) -> usize {
+        self.peak_bytes_in_use.get()
+    }
+
+    fn set_bytes_in_use(&self, bytes: usize) {
+        self.bytes_in_use.set(bytes);
+        self.peak_bytes_in_use
+            .set(self.peak_bytes_in_use.get().max(bytes));
+    }
+}
+
+unsafe impl<A: MyAllocator> MyAllocator for CountingAllocator<A> {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let ptr = self.inner.allocate(layout)?;
+        self.allocations.set(self.allocations.get() + 1);
+        self.set_bytes_in_use(self.bytes_in_use.get() + layout.size());
+        Some(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { self.inner.deallocate(ptr, layout) };
+        self.deallocations.set(self.deallocations.get() + 1);
+        self.set_bytes_in_use(self.bytes_in_use.get() - layout.size());
+    }
+
+    unsafe fn reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        let new_ptr = unsafe { self.inner.reallocate(ptr, old_layout, new_layout)? };
+        self.reallocations.set(self.reallocations.get() + 1);
+        self.set_bytes_in_use(self.bytes_in_use.get() - old_layout.size() + new_layout.size());
+        Some(new_ptr)
+    }
+}
+
+/// A bump arena: hands out blocks from one fixed-size region by bumping an offset.
+///
+/// Freeing only gives memory back if it's the most recent block, so this is best for
+/// short-lived collections that all get thrown away together. Growing the most recent
+/// block happens in place.
+pub struct BumpAllocator {
+    start: NonNull<u8>,
+    capacity: usize,
+    // how many bytes from the start are handed out
+    offset: Cell<usize>,
+    // the most recently allocated block, which is the only one we can grow in place or take back
+    last: Cell<Option<NonNull<u8>>>,
+}
+
+impl BumpAllocator {
+    // the alignment of the arena itself. Blocks with stricter alignment than this still work,
+    // they just waste some space on padding
+    const ARENA_ALIGN: usize = 16;
+
+    /// Creates an arena with room for `capacity` bytes, taken from the global allocator.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let start = if capacity == 0 {
+            NonNull::<u128>::dangling().cast()
+        } else {
+            let layout = Self::arena_layout(capacity);
+            // SAFETY: the layout has a non-zero size
+            let ptr = unsafe { alloc::alloc::alloc(layout) };
+            NonNull::new(ptr).unwrap_or_else(|| alloc::alloc::handle_alloc_error(layout))
+        };
+
+        Self {
+            start,
+            capacity,
+            offset: Cell::new(0),
+            last: Cell::new(None),
+        }
+    }
+
+    /// How many bytes of the arena are in use, including padding.
+    pub fn used(&self) -> usize {
+        self.offset.get()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Whether `ptr` points into this arena.
+    pub fn contains(&self, ptr: NonNull<u8>) -> bool {
+        let start = self.start.as_ptr() as usize;
+        (start..start + self.capacity).contains(&(ptr.as_ptr() as usize))
+    }
+
+    fn arena_layout(capacity: usize) -> Layout {
+        Layout::from_size_align(capacity, Self::ARENA_ALIGN).expect("arena too large")
+    }
+
+    /// The offset of `ptr` from the start of the arena.
+    fn offset_of(&self, ptr: NonNull<u8>) -> usize {
+        assert!(
+            self.contains(ptr),
+            "pointer wasn't allocated by this BumpAllocator"
+        );
+        ptr.as_ptr() as usize - self.start.as_ptr() as usize
+    }
+}
+
+unsafe impl MyAllocator for BumpAllocator {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let start = self.start.as_ptr() as usize;
+        let unaligned = start.checked_add(self.offset.get())?;
+        let aligned = unaligned.checked_next_multiple_of(layout.align())?;
+
+        let begin = aligned - start;
+        let end = begin.checked_add(layout.size())?;
+        if end > self.capacity {
+            return None;
+        }
+
+        self.offset.set(end);
+        // SAFETY: `begin` is within the arena, so this stays in bounds of the same allocation
+        let ptr = unsafe { NonNull::new_unchecked(self.start.as_ptr().add(begin)) };
+        self.last.set(Some(ptr));
+        Some(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let begin = self.offset_of(ptr);
+
+        // we can only reuse the space if nothing was allocated after this block
+        if self.last.get() == Some(ptr) {
+            debug_assert_eq!(begin + layout.size(), self.offset.get());
+            self.offset.set(begin);
+            self.last.set(None);
+        }
+    }
+
+    unsafe fn reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Option<NonNull<u8>> {
+        let begin = self.offset_of(ptr);
+
+        if self.last.get() == Some(ptr) {
+            // the most recent block can just grow (or shrink) where it is
+            let end = begin.checked_add(new_layout.size())?;
+            if end > self.capacity {
+                return None;
+            }
+            self.offset.set(end);
+            return Some(ptr);
+        }
+
+        let new_ptr = self.allocate(new_layout)?;
+        // SAFETY: the new block was just bumped past everything else, so they don't overlap
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                ptr.as_ptr(),
+                new_ptr.as_ptr(),
+                old_layout.size().min(new_layout.size()),
+            );
+        }
+        Some(new_ptr)
+    }
+}
+
+impl Drop for BumpAllocator {
+    fn drop(&mut self) {
+        if self.capacity != 0 {
+            // SAFETY: we allocated the arena ourselves with this layout
+            unsafe {
+                alloc::alloc::dealloc(self.start.as_ptr(), Self::arena_layout(self.capacity))
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MyVec, TryReserveErrorKind};
+
+    #[test]
+    fn test_counting_allocator() {
+        let counter = CountingAllocator::new();
+
+        let mut vec = MyVec::new_in(&counter);
+        for i in 0..100u64 {
+            vec.push(i);
+        }
+        assert_eq!(counter.allocations(), 1);
+        assert!(counter.reallocations() > 0);
+        assert_eq!(counter.bytes_in_use(), vec.capacity() * 8);
+
+        vec.shrink_to_fit();
+        assert_eq!(counter.bytes_in_use(), 100 * 8);
+
+        drop(vec);
+        assert_eq!(counter.live_allocations(), 0);
+        assert_eq!(counter.bytes_in_use(), 0);
+        assert_eq!(counter.peak_bytes_in_use(), 128 * 8);
+    }
+
+    #[test]
+    fn test_released_to_the_right_allocator() {
+        let first = CountingAllocator::new();
+        let second = CountingAllocator::new();
+
+        let mut a = MyVec::with_capacity_in(4, &first);
+        let mut b = MyVec::with_capacity_in(4, &second);
+        for i in 0..10 {
+            a.push(i.to_string());
+            b.push(i.to_string());
+        }
+
+        // moving elements between vectors doesn't move their buffers
+        a.append(&mut b);
+        let tail = a.split_off(15);
+        assert_eq!(tail.len(), 5);
+        assert_eq!(first.live_allocations(), 2);
+        assert_eq!(second.live_allocations(), 1);
+
+        // `into_iter` takes the buffer (and the allocator) with it
+        let mut iter = a.into_iter();
+        assert_eq!(iter.next().as_deref(), Some("0"));
+        drop(iter);
+        assert_eq!(first.live_allocations(), 1);
+
+        drop(tail);
+        drop(b);
+        assert_eq!(first.live_allocations(), 0);
+        assert_eq!(second.live_allocations(), 0);
+        assert_eq!(first.bytes_in_use() + second.bytes_in_use(), 0);
+    }
+
+    #[test]
+    fn test_zero_sized_never_allocates() {
+        let counter = CountingAllocator::new();
+        let mut vec = MyVec::new_in(&counter);
+        for _ in 0..1000 {
+            vec.push(());
+        }
+        vec.shrink_to_fit();
+        drop(vec);
+        assert_eq!(counter.allocations(), 0);
+    }
+
+    #[test]
+    fn test_bump_allocator() {
+        let bump = BumpAllocator::with_capacity(1024);
+
+        // the only block in the arena grows in place, so the arena's usage tracks the capacity
+        let mut vec = MyVec::new_in(&bump);
+        for i in 0..64u32 {
+            vec.push(i);
+        }
+        assert_eq!(bump.used(), vec.capacity() * 4);
+        assert!(bump.contains(NonNull::new(vec.as_mut_ptr().cast()).unwrap()));
+
+        let mut other = MyVec::new_in(&bump);
+        other.push(1u8);
+        assert!(bump.used() > vec.capacity() * 4);
+
+        // the most recent block is handed back when it's freed
+        let used = bump.used();
+        drop(other);
+        assert!(bump.used() < used);
+
+        // `vec` is no longer the most recent block, so growing it copies to a new block
+        let mut third = MyVec::new_in(&bump);
+        third.push(0u16);
+        let old_ptr = vec.as_mut_ptr();
+        let used = bump.used();
+        vec.reserve(vec.capacity() + 1);
+        assert_ne!(vec.as_mut_ptr(), old_ptr);
+        // `used` is already a multiple of 4, so there's no padding before the new block
+        assert_eq!(bump.used(), used + vec.capacity() * 4);
+        assert!(vec.iter().copied().eq(0..64));
+    }
+
+    #[test]
+    fn test_bump_allocator_full() {
+        let bump = BumpAllocator::with_capacity(64);
+        let mut vec = MyVec::new_in(&bump);
+        for i in 0..16u32 {
+            vec.try_push(i).unwrap();
+        }
+
+        let (value, err) = vec.try_push(16).unwrap_err();
+        assert_eq!(value, 16);
+        assert!(matches!(err.kind(), TryReserveErrorKind::AllocError { .. }));
+        assert_eq!(vec.len(), 16);
+    }
+
+    #[test]
+    fn test_counting_bump_allocator() {
+        let counter = CountingAllocator::wrapping(BumpAllocator::with_capacity(4096));
+        {
+            let mut words = MyVec::new_in(&counter);
+            let mut numbers = MyVec::new_in(&counter);
+            for i in 0..50 {
+                words.push(i.to_string());
+                numbers.push(i);
+            }
+            numbers.retain(|x| x % 3 == 0);
+            numbers.shrink_to_fit();
+            words.truncate(5);
+        }
+        assert_eq!(counter.live_allocations(), 0);
+        assert_eq!(counter.bytes_in_use(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "pointer wasn't allocated by this BumpAllocator")]
+    fn test_bump_allocator_rejects_foreign_pointers() {
+        let bump = BumpAllocator::with_capacity(64);
+        let mut value = 0u64;
+        let ptr = NonNull::from(&mut value).cast();
+        unsafe { bump.deallocate(ptr, Layout::new::<u64>()) };
+    }
+}
@@ -0,0 +1,330 @@
+//! An append-only vector that many threads can push to at once.
+
+use core::alloc::Layout;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+use crate::{capacity_overflow, Global, MyAllocator};
+
+/// How big the first bucket is (as a power of two). Every bucket after it is twice as big as the
+/// one before, so bucket `b` holds `FIRST_BUCKET << b` elements.
+const FIRST_BUCKET_BITS: u32 = 5;
+const FIRST_BUCKET: usize = 1 << FIRST_BUCKET_BITS;
+
+/// Enough buckets to hold `usize::MAX` elements, more or less.
+const BUCKETS: usize = (usize::BITS - FIRST_BUCKET_BITS) as usize;
+
+struct Slot<T> {
+    // set once `value` has been written, so readers know they can look at it
+    ready: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// A vector that can be pushed to through a shared reference, from any number of threads at once,
+/// without a lock. Elements can't be removed or changed once they're in.
+///
+/// Where `MyVec` reallocates and moves everything when it fills up, this adds a new bucket twice
+/// the size of the last one and leaves the old buckets alone. So elements never move, and a `&T`
+/// from [`ConcurrentAppendVec::get`] stays valid while other threads keep pushing.
+///
+/// Pushing is a `fetch_add` to claim an index, plus a compare-and-swap the first time anyone
+/// touches a new bucket to decide whose allocation gets used.
+pub struct ConcurrentAppendVec<T> {
+    buckets: [AtomicPtr<Slot<T>>; BUCKETS],
+    // how many indices have been handed out, some of which might not be written yet
+    reserved: AtomicUsize,
+}
+
+// SAFETY: sharing the vector lets other threads push `T`s in (so they move between threads) and
+//         get `&T`s out (so they're shared between threads)
+unsafe impl<T: Send + Sync> Sync for ConcurrentAppendVec<T> {}
+unsafe impl<T: Send> Send for ConcurrentAppendVec<T> {}
+
+impl<T> ConcurrentAppendVec<T> {
+    pub const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicPtr::new(ptr::null_mut()) }; BUCKETS],
+            reserved: AtomicUsize::new(0),
+        }
+    }
+
+    /// Which bucket `index` is in, and where in it.
+    fn locate(index: usize) -> (usize, usize) {
+        // shifting everything up by the first bucket's size makes bucket `b` start at
+        // `FIRST_BUCKET << b`, i.e. the highest set bit says which bucket it is
+        let shifted = index
+            .checked_add(FIRST_BUCKET)
+            .unwrap_or_else(|| capacity_overflow());
+        let bucket = (usize::BITS - 1 - shifted.leading_zeros() - FIRST_BUCKET_BITS) as usize;
+        (bucket, shifted - (FIRST_BUCKET << bucket))
+    }
+
+    fn bucket_layout(bucket: usize) -> Layout {
+        Layout::array::<Slot<T>>(FIRST_BUCKET << bucket).unwrap_or_else(|_| capacity_overflow())
+    }
+
+    /// Adds `value` to the end, and returns its index.
+    pub fn push(&self, value: T) -> usize {
+        let index = self.reserved.fetch_add(1, Ordering::Relaxed);
+        let (bucket, offset) = Self::locate(index);
+        let slots = self.bucket(bucket);
+
+        // SAFETY: `offset` is inside the bucket, and nobody else got this index, so nobody else
+        //         writes this slot, and nobody reads it until `ready` is set
+        unsafe {
+            let slot = &*slots.as_ptr().add(offset);
+            (*slot.value.get()).write(value);
+            // `Release` so whoever sees `ready` also sees the value
+            slot.ready.store(true, Ordering::Release);
+        }
+        index
+    }
+
+    /// The slots of bucket `bucket`, allocating them if nobody has yet.
+    fn bucket(&self, bucket: usize) -> NonNull<Slot<T>> {
+        let existing = self.buckets[bucket].load(Ordering::Acquire);
+        if let Some(slots) = NonNull::new(existing) {
+            return slots;
+        }
+
+        let layout = Self::bucket_layout(bucket);
+        let new = Global
+            .allocate(layout)
+            .unwrap_or_else(|| alloc::alloc::handle_alloc_error(layout))
+            .cast::<Slot<T>>();
+        for offset in 0..FIRST_BUCKET << bucket {
+            // SAFETY: the allocation is big enough for the whole bucket
+            unsafe {
+                new.as_ptr().add(offset).write(Slot {
+                    ready: AtomicBool::new(false),
+                    value: UnsafeCell::new(MaybeUninit::uninit()),
+                })
+            };
+        }
+
+        // several threads can get here for the same bucket, but only one of them gets to put its
+        // allocation in, and the others use that one instead of theirs
+        match self.buckets[bucket].compare_exchange(
+            ptr::null_mut(),
+            new.as_ptr(),
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => new,
+            Err(winner) => {
+                // SAFETY: nobody else ever saw `new`, and none of its slots hold values
+                unsafe { Global.deallocate(new.cast(), layout) };
+                // SAFETY: it wasn't null, or the exchange would have worked
+                unsafe { NonNull::new_unchecked(winner) }
+            }
+        }
+    }
+
+    /// The element at `index`, or `None` if it hasn't been pushed yet (or is still being pushed
+    /// by another thread).
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.reserved.load(Ordering::Acquire) {
+            return None;
+        }
+        let (bucket, offset) = Self::locate(index);
+        let slots = NonNull::new(self.buckets[bucket].load(Ordering::Acquire))?;
+
+        // SAFETY: `offset` is inside the bucket, and once `ready` is set the value is never
+        //         written again, and lives as long as the vector does
+        unsafe {
+            let slot = &*slots.as_ptr().add(offset);
+            if slot.ready.load(Ordering::Acquire) {
+                Some((*slot.value.get()).assume_init_ref())
+            } else {
+                None
+            }
+        }
+    }
+
+    /// How many pushes have started. All of them have finished if nobody is pushing right now,
+    /// but otherwise a few of the last ones might still be missing.
+    pub fn len(&self) -> usize {
+        self.reserved.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The elements in index order, skipping any that are still being pushed.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        (0..self.len()).filter_map(|index| self.get(index))
+    }
+}
+
+impl<T> Default for ConcurrentAppendVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ConcurrentAppendVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Extend<T> for ConcurrentAppendVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for ConcurrentAppendVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = Self::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T> Drop for ConcurrentAppendVec<T> {
+    fn drop(&mut self) {
+        for (bucket, slots) in self.buckets.iter_mut().enumerate() {
+            let Some(slots) = NonNull::new(*slots.get_mut()) else {
+                continue;
+            };
+            for offset in 0..FIRST_BUCKET << bucket {
+                // SAFETY: we have `&mut self`, so every push has finished, and the slots that are
+                //         ready hold values nobody else owns
+                unsafe {
+                    let slot = &mut *slots.as_ptr().add(offset);
+                    if *slot.ready.get_mut() {
+                        slot.value.get_mut().assume_init_drop();
+                    }
+                }
+            }
+            // SAFETY: we allocated it with this layout
+            unsafe { Global.deallocate(slots.cast(), Self::bucket_layout(bucket)) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::test_utils::DropCounter;
+
+    #[cfg(not(miri))]
+    const N_PUSHES: usize = 20_000;
+    #[cfg(not(miri))]
+    const N_THREADS: usize = 16;
+
+    #[cfg(miri)]
+    const N_PUSHES: usize = 100;
+    #[cfg(miri)]
+    const N_THREADS: usize = 4;
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(ConcurrentAppendVec::<u8>::locate(0), (0, 0));
+        assert_eq!(ConcurrentAppendVec::<u8>::locate(31), (0, 31));
+        assert_eq!(ConcurrentAppendVec::<u8>::locate(32), (1, 0));
+        assert_eq!(ConcurrentAppendVec::<u8>::locate(95), (1, 63));
+        assert_eq!(ConcurrentAppendVec::<u8>::locate(96), (2, 0));
+        let (bucket, _) = ConcurrentAppendVec::<u8>::locate(usize::MAX - FIRST_BUCKET);
+        assert_eq!(bucket, BUCKETS - 1);
+    }
+
+    #[test]
+    fn test_push_get() {
+        let vec = ConcurrentAppendVec::new();
+        assert!(vec.is_empty());
+        assert_eq!(vec.get(0), None);
+        for i in 0..1000 {
+            assert_eq!(vec.push(i * 2), i);
+        }
+        assert_eq!(vec.len(), 1000);
+        assert_eq!(vec.get(999), Some(&1998));
+        assert_eq!(vec.get(1000), None);
+        assert!(vec.iter().copied().eq((0..1000).map(|i| i * 2)));
+
+        let collected: ConcurrentAppendVec<_> = "abc".chars().collect();
+        assert_eq!(format!("{collected:?}"), "['a', 'b', 'c']");
+    }
+
+    #[test]
+    fn test_references_stay_put() {
+        let vec = ConcurrentAppendVec::new();
+        vec.push(String::from("first"));
+        let first = vec.get(0).unwrap();
+        // plenty of new buckets, and `first` is still fine
+        for i in 0..10_000 {
+            vec.push(i.to_string());
+        }
+        assert_eq!(first, "first");
+        assert!(ptr::eq(first, vec.get(0).unwrap()));
+    }
+
+    #[test]
+    fn test_drops() {
+        let counter = DropCounter::new();
+        let vec = ConcurrentAppendVec::new();
+        for i in 0..100 {
+            vec.push(counter.track(i));
+        }
+        drop(vec);
+        assert_eq!(counter.drops(), 100);
+    }
+
+    #[test]
+    fn test_threads_pushing_at_once() {
+        // like `data_race::attempt5` in wk8_00_concurrency, but every thread pushes instead of
+        // locking a counter, and there's no lock anywhere
+        let vec = ConcurrentAppendVec::new();
+        let indices = Mutex::new(Vec::new());
+
+        std::thread::scope(|scope| {
+            for thread in 0..N_THREADS {
+                let vec = &vec;
+                let indices = &indices;
+                scope.spawn(move || {
+                    let mut mine = Vec::with_capacity(N_PUSHES);
+                    for i in 0..N_PUSHES {
+                        let index = vec.push((thread, i));
+                        // what we just pushed is there straight away, even though other threads
+                        // are growing the vector at the same time
+                        assert_eq!(vec.get(index), Some(&(thread, i)));
+                        mine.push(index);
+                    }
+                    // and references taken early on still point at the right thing
+                    let first = vec.get(mine[0]).unwrap();
+                    assert_eq!(*first, (thread, 0));
+                    indices.lock().unwrap().extend(mine);
+                });
+            }
+
+            // a reader that keeps checking what it can see while everyone else pushes
+            scope.spawn(|| {
+                while vec.len() < N_THREADS * N_PUSHES {
+                    for (thread, i) in vec.iter() {
+                        assert!(*thread < N_THREADS && *i < N_PUSHES);
+                    }
+                }
+            });
+        });
+
+        assert_eq!(vec.len(), N_THREADS * N_PUSHES);
+        // every index was handed out exactly once
+        let mut indices = indices.into_inner().unwrap();
+        indices.sort_unstable();
+        assert!(indices.iter().copied().eq(0..N_THREADS * N_PUSHES));
+        // and every thread's values are all there, in the order it pushed them
+        for thread in 0..N_THREADS {
+            let values: Vec<_> = vec.iter().filter(|value| value.0 == thread).collect();
+            assert!(values.iter().map(|value| value.1).eq(0..N_PUSHES));
+        }
+    }
+}
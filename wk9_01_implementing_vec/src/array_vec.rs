@@ -0,0 +1,394 @@
+//! A fixed-capacity vector that never touches the heap.
+
+use core::fmt;
+use core::iter::FusedIterator;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+
+use crate::{Iter, IterMut};
+
+/// A vector with room for exactly `N` elements, stored inline in a `[MaybeUninit<T>; N]`.
+///
+/// It never allocates, so running out of room is an error you get back (along with the value
+/// that didn't fit) rather than a reallocation.
+pub struct MyArrayVec<T, const N: usize> {
+    // only `buf[..len]` is initialised
+    buf: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> MyArrayVec<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            // an array of `MaybeUninit` doesn't need initialising
+            buf: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Adds `value` to the end, or gives it back if the vector is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(value);
+        }
+
+        self.buf[self.len].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Inserts `value` at `index`, shifting everything after it to the right,
+    /// or gives it back if the vector is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn try_insert(&mut self, index: usize, value: T) -> Result<(), T> {
+        let len = self.len;
+        if index > len {
+            panic!("insertion index (is {index}) should be <= len (is {len})");
+        }
+        if self.is_full() {
+            return Err(value);
+        }
+
+        // SAFETY: `index <= len < N`, so both the shifted range and the new slot are inside the array
+        unsafe {
+            let ptr = self.as_mut_ptr().add(index);
+            core::ptr::copy(ptr, ptr.add(1), len - index);
+            ptr.write(value);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+        // SAFETY: `len` was initialised, and is now outside the initialised prefix so it won't be read again
+        Some(unsafe { self.buf[self.len].assume_init_read() })
+    }
+
+    /// Removes and returns the element at `index`, shifting everything after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len;
+        if index >= len {
+            panic!("removal index (is {index}) should be < len (is {len})");
+        }
+
+        // SAFETY: `index < len`, so the element is initialised, and the hole it leaves is
+        //         immediately filled by the shift
+        unsafe {
+            let ptr = self.as_mut_ptr().add(index);
+            let value = ptr.read();
+            core::ptr::copy(ptr.add(1), ptr, len - index - 1);
+            self.len -= 1;
+            value
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    /// Drops every element from index `len` onwards.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+
+        let tail_len = self.len - len;
+        // shrink first, so if a destructor panics we don't try to drop the tail again
+        self.len = len;
+        // SAFETY: `len..len + tail_len` was initialised and is now outside the vector
+        unsafe {
+            let tail = core::ptr::slice_from_raw_parts_mut(self.as_mut_ptr().add(len), tail_len);
+            tail.drop_in_place();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements are initialised, and `MaybeUninit<T>` has the same layout as `T`
+        unsafe { core::slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: same as `as_slice`
+        unsafe { core::slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self.as_slice())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut::new(self.as_mut_slice())
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.buf.as_mut_ptr().cast()
+    }
+}
+
+impl<T, const N: usize> Drop for MyArrayVec<T, N> {
+    fn drop(&mut self) {
+        // `MaybeUninit` never drops what it holds, so only the initialised prefix gets dropped, by us
+        self.clear();
+    }
+}
+
+impl<T, const N: usize> Default for MyArrayVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for MyArrayVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for MyArrayVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for MyArrayVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for MyArrayVec<T, N> {
+    fn clone(&self) -> Self {
+        let mut vec = Self::new();
+        for value in self {
+            // can't fail, `vec` has the same capacity as `self`. If a `clone` panics,
+            // `vec` drops the clones made so far
+            let _ = vec.push(value.clone());
+        }
+        vec
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for MyArrayVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for MyArrayVec<T, N> {}
+
+/// Owning iterator over a [`MyArrayVec`].
+pub struct IntoIter<T, const N: usize> {
+    // `buf[start..end]` hasn't been yielded yet
+    buf: [MaybeUninit<T>; N],
+    start: usize,
+    end: usize,
+}
+
+impl<T, const N: usize> IntoIterator for MyArrayVec<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> IntoIter<T, N> {
+        let this = ManuallyDrop::new(self);
+        IntoIter {
+            // SAFETY: `this` is never used or dropped again, so the elements are only owned once
+            buf: unsafe { core::ptr::read(&this.buf) },
+            start: 0,
+            end: this.len,
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a MyArrayVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut MyArrayVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        // SAFETY: `start < end`, so this element is initialised and not yet yielded
+        let value = unsafe { self.buf[self.start].assume_init_read() };
+        self.start += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        self.end -= 1;
+        // SAFETY: same as `next`
+        Some(unsafe { self.buf[self.end].assume_init_read() })
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> Drop for IntoIter<T, N> {
+    fn drop(&mut self) {
+        // SAFETY: `start..end` is initialised and hasn't been yielded
+        unsafe {
+            let remaining = core::ptr::slice_from_raw_parts_mut(
+                self.buf.as_mut_ptr().cast::<T>().add(self.start),
+                self.end - self.start,
+            );
+            self.start = self.end;
+            remaining.drop_in_place();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_panics, DropCounter};
+
+    #[test]
+    fn test_push_pop() {
+        let mut vec = MyArrayVec::<i32, 3>::new();
+        assert_eq!(vec.push(1), Ok(()));
+        assert_eq!(vec.push(2), Ok(()));
+        assert_eq!(vec.push(3), Ok(()));
+        assert!(vec.is_full());
+        assert_eq!(vec.push(4), Err(4));
+        assert_eq!(vec[..], [1, 2, 3]);
+
+        assert_eq!(vec.pop(), Some(3));
+        assert_eq!(vec.get(1), Some(&2));
+        assert_eq!(vec.get(2), None);
+
+        let mut empty = MyArrayVec::<String, 0>::new();
+        assert_eq!(empty.push("x".to_string()), Err("x".to_string()));
+        assert_eq!(empty.pop(), None);
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut vec = MyArrayVec::<i32, 4>::new();
+        vec.try_insert(0, 2).unwrap();
+        vec.try_insert(0, 0).unwrap();
+        vec.try_insert(1, 1).unwrap();
+        vec.try_insert(3, 3).unwrap();
+        assert_eq!(vec[..], [0, 1, 2, 3]);
+        assert_eq!(vec.try_insert(0, 9), Err(9));
+
+        assert_eq!(vec.remove(1), 1);
+        assert_eq!(vec.remove(2), 3);
+        assert_eq!(vec[..], [0, 2]);
+
+        assert_panics(|| vec.remove(2));
+        assert_panics(|| vec.try_insert(3, 0));
+    }
+
+    #[test]
+    fn test_slices_and_iterators() {
+        let mut vec = MyArrayVec::<i32, 8>::new();
+        for x in [3, 1, 2] {
+            vec.push(x).unwrap();
+        }
+        vec.sort();
+        for x in &mut vec {
+            *x += 10;
+        }
+        assert_eq!(vec.iter().copied().collect::<Vec<_>>(), [11, 12, 13]);
+        assert_eq!(
+            vec.clone().into_iter().rev().collect::<Vec<_>>(),
+            [13, 12, 11]
+        );
+        assert_eq!(format!("{vec:?}"), "[11, 12, 13]");
+    }
+
+    #[test]
+    fn test_drops_only_initialised() {
+        let counter = DropCounter::new();
+
+        let mut vec = MyArrayVec::<_, 8>::new();
+        for i in 0..5 {
+            vec.push(counter.track(i)).unwrap();
+        }
+        drop(vec.remove(0));
+        vec.truncate(3);
+        assert_eq!(counter.drops(), 2);
+        drop(vec);
+        assert_eq!(counter.drops(), 5);
+
+        let mut vec = MyArrayVec::<_, 8>::new();
+        for i in 0..4 {
+            vec.push(counter.track(i)).unwrap();
+        }
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.next().map(|x| x.value), Some(0));
+        assert_eq!(counter.drops(), 6);
+        drop(iter);
+        assert_eq!(counter.drops(), 9);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let mut vec = MyArrayVec::<(), 2>::new();
+        assert_eq!(vec.push(()), Ok(()));
+        assert_eq!(vec.push(()), Ok(()));
+        assert_eq!(vec.push(()), Err(()));
+        assert_eq!(vec.into_iter().count(), 2);
+    }
+}
@@ -0,0 +1,699 @@
+//! Priority queues on top of `MyVec`.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use crate::MyVec;
+
+/// Which end of the ordering a heap hands out first.
+pub trait HeapOrder {
+    /// Whether `a` should come out of the heap before `b`.
+    fn outranks<T: Ord + ?Sized>(a: &T, b: &T) -> bool;
+}
+
+/// Biggest first, like `BinaryHeap`. The default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Max;
+
+/// Smallest first, like a `BinaryHeap` of `Reverse`s.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Min;
+
+impl HeapOrder for Max {
+    fn outranks<T: Ord + ?Sized>(a: &T, b: &T) -> bool {
+        a > b
+    }
+}
+
+impl HeapOrder for Min {
+    fn outranks<T: Ord + ?Sized>(a: &T, b: &T) -> bool {
+        a < b
+    }
+}
+
+/// A priority queue, like `BinaryHeap<T>`, stored as an implicit binary tree in a `MyVec`:
+/// the children of `data[i]` are `data[2i + 1]` and `data[2i + 2]`, and no child outranks its parent.
+///
+/// It's a max-heap by default. For a min-heap use [`MyBinaryHeap::new_min`], or wrap the
+/// elements in [`std::cmp::Reverse`] like you would with `BinaryHeap`:
+///
+/// ```
+/// use std::cmp::Reverse;
+/// use wk9_01_implementing_vec::binary_heap::MyBinaryHeap;
+///
+/// let mut min = MyBinaryHeap::new_min();
+/// let mut reversed = MyBinaryHeap::new();
+/// for x in [3, 1, 2] {
+///     min.push(x);
+///     reversed.push(Reverse(x));
+/// }
+/// assert_eq!(min.pop(), Some(1));
+/// assert_eq!(reversed.pop(), Some(Reverse(1)));
+/// ```
+pub struct MyBinaryHeap<T, O: HeapOrder = Max> {
+    data: MyVec<T>,
+    _order: PhantomData<fn() -> O>,
+}
+
+impl<T: Ord> MyBinaryHeap<T> {
+    pub fn new() -> Self {
+        Self::with_order()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_vec_unchecked(MyVec::with_capacity(capacity))
+    }
+}
+
+impl<T: Ord> MyBinaryHeap<T, Min> {
+    /// Creates an empty heap that hands out the smallest element first.
+    pub fn new_min() -> Self {
+        Self::with_order()
+    }
+}
+
+impl<T: Ord, O: HeapOrder> MyBinaryHeap<T, O> {
+    /// Creates an empty heap ordered by `O`.
+    pub fn with_order() -> Self {
+        Self::from_vec_unchecked(MyVec::new())
+    }
+
+    /// Builds a heap out of `data` in O(n), by sifting down every parent from the bottom up.
+    pub fn heapify(data: MyVec<T>) -> Self {
+        let mut heap = Self::from_vec_unchecked(data);
+        for index in (0..heap.len() / 2).rev() {
+            heap.sift_down(index);
+        }
+        heap
+    }
+
+    fn from_vec_unchecked(data: MyVec<T>) -> Self {
+        Self {
+            data,
+            _order: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.data.push(value);
+        self.sift_up(self.len() - 1);
+    }
+
+    /// Removes and returns the element that ranks highest.
+    pub fn pop(&mut self) -> Option<T> {
+        let last = self.data.pop()?;
+        if self.is_empty() {
+            return Some(last);
+        }
+        let top = core::mem::replace(&mut self.data[0], last);
+        self.sift_down(0);
+        Some(top)
+    }
+
+    /// The element that ranks highest.
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// A mutable reference to the top element. The heap is fixed up when the `PeekMut` is dropped,
+    /// so changing the element's priority is fine.
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, O>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(PeekMut { heap: self })
+        }
+    }
+
+    /// The elements in order, such that popping from the back of the vector gives the same order
+    /// as popping from the heap (so ascending for a max-heap).
+    pub fn into_sorted_vec(mut self) -> MyVec<T> {
+        // heap sort: swap the top to the end, and shrink the heap part by one
+        for end in (1..self.len()).rev() {
+            self.data.swap(0, end);
+            self.sift_down_within(0, end);
+        }
+        self.data
+    }
+
+    /// The elements in no particular order.
+    pub fn into_vec(self) -> MyVec<T> {
+        self.data
+    }
+
+    /// The elements in heap order (which is no particular order).
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn iter(&self) -> crate::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// Moves `data[index]` up until its parent outranks it.
+    fn sift_up(&mut self, mut index: usize) {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if !O::outranks(&self.data[index], &self.data[parent]) {
+                break;
+            }
+            self.data.swap(index, parent);
+            index = parent;
+        }
+    }
+
+    fn sift_down(&mut self, index: usize) {
+        self.sift_down_within(index, self.len());
+    }
+
+    /// Moves `data[index]` down until it outranks both its children, only looking at `data[..end]`.
+    ///
+    /// Everything's moved with swaps, so if `Ord` panics partway the heap might be out of order,
+    /// but no element is lost or duplicated.
+    fn sift_down_within(&mut self, mut index: usize, end: usize) {
+        loop {
+            let left = 2 * index + 1;
+            if left >= end {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < end && O::outranks(&self.data[right], &self.data[left]) {
+                right
+            } else {
+                left
+            };
+            if !O::outranks(&self.data[child], &self.data[index]) {
+                break;
+            }
+            self.data.swap(index, child);
+            index = child;
+        }
+    }
+}
+
+/// A mutable reference to the top of a [`MyBinaryHeap`], from [`MyBinaryHeap::peek_mut`].
+/// Puts the element back in its place when dropped.
+pub struct PeekMut<'a, T: Ord, O: HeapOrder = Max> {
+    heap: &'a mut MyBinaryHeap<T, O>,
+}
+
+impl<T: Ord, O: HeapOrder> PeekMut<'_, T, O> {
+    /// Removes the peeked element from the heap and returns it.
+    pub fn pop(this: Self) -> T {
+        let this = core::mem::ManuallyDrop::new(this);
+        // SAFETY: `this` is never used or dropped again, so the `&mut` is only used once
+        let heap = unsafe { core::ptr::read(&this.heap) };
+        heap.pop().unwrap()
+    }
+}
+
+impl<T: Ord, O: HeapOrder> Deref for PeekMut<'_, T, O> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.heap.data[0]
+    }
+}
+
+impl<T: Ord, O: HeapOrder> DerefMut for PeekMut<'_, T, O> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.heap.data[0]
+    }
+}
+
+impl<T: Ord, O: HeapOrder> Drop for PeekMut<'_, T, O> {
+    fn drop(&mut self) {
+        // the top might not outrank its children any more
+        self.heap.sift_down(0);
+    }
+}
+
+impl<T: Ord> Default for MyBinaryHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, O: HeapOrder> Clone for MyBinaryHeap<T, O> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            _order: PhantomData,
+        }
+    }
+}
+
+impl<T: fmt::Debug, O: HeapOrder> fmt::Debug for MyBinaryHeap<T, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.iter()).finish()
+    }
+}
+
+impl<T: Ord> From<MyVec<T>> for MyBinaryHeap<T> {
+    /// Heapifies the vector in place, in O(n).
+    fn from(vec: MyVec<T>) -> Self {
+        Self::heapify(vec)
+    }
+}
+
+impl<T: Ord, const N: usize> From<[T; N]> for MyBinaryHeap<T> {
+    fn from(array: [T; N]) -> Self {
+        Self::heapify(MyVec::from(array))
+    }
+}
+
+impl<T, O: HeapOrder> From<MyBinaryHeap<T, O>> for MyVec<T> {
+    fn from(heap: MyBinaryHeap<T, O>) -> Self {
+        heap.data
+    }
+}
+
+impl<T: Ord> FromIterator<T> for MyBinaryHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::heapify(iter.into_iter().collect())
+    }
+}
+
+impl<T: Ord, O: HeapOrder> Extend<T> for MyBinaryHeap<T, O> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T, O: HeapOrder> IntoIterator for MyBinaryHeap<T, O> {
+    type Item = T;
+    type IntoIter = crate::IntoIter<T>;
+
+    /// The elements in no particular order.
+    fn into_iter(self) -> crate::IntoIter<T> {
+        self.data.into_iter()
+    }
+}
+
+/// A heap of keys `0..n` (e.g. graph nodes) with priorities, that can find any key's place in the
+/// heap, so it can change its priority in O(log n). That's what Dijkstra's algorithm and Prim's
+/// algorithm want, so unlike [`MyBinaryHeap`] it's a min-heap by default.
+///
+/// ```
+/// use wk9_01_implementing_vec::binary_heap::MyIndexedHeap;
+///
+/// let mut heap = MyIndexedHeap::new();
+/// heap.push(0, 10);
+/// heap.push(1, 5);
+/// heap.decrease_key(0, 1);
+/// assert_eq!(heap.pop(), Some((0, 1)));
+/// assert_eq!(heap.pop(), Some((1, 5)));
+/// ```
+pub struct MyIndexedHeap<P, O: HeapOrder = Min> {
+    // the heap itself, as `(key, priority)` pairs
+    heap: MyVec<(usize, P)>,
+    // `positions[key]` is where `key` is in `heap`, if it's in there
+    positions: MyVec<Option<usize>>,
+    _order: PhantomData<fn() -> O>,
+}
+
+impl<P: Ord> MyIndexedHeap<P> {
+    pub fn new() -> Self {
+        Self::with_order()
+    }
+}
+
+impl<P: Ord, O: HeapOrder> MyIndexedHeap<P, O> {
+    /// Creates an empty heap ordered by `O`.
+    pub fn with_order() -> Self {
+        Self {
+            heap: MyVec::new(),
+            positions: MyVec::new(),
+            _order: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains(&self, key: usize) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// The priority `key` currently has, if it's in the heap.
+    pub fn priority(&self, key: usize) -> Option<&P> {
+        Some(&self.heap[self.position(key)?].1)
+    }
+
+    /// Adds `key` with `priority`, or changes its priority if it's already in the heap
+    /// (returning the old one).
+    pub fn push(&mut self, key: usize, priority: P) -> Option<P> {
+        if let Some(position) = self.position(key) {
+            return Some(self.change_priority_at(position, priority));
+        }
+
+        if key >= self.positions.len() {
+            let missing = key + 1 - self.positions.len();
+            self.positions.extend(core::iter::repeat_n(None, missing));
+        }
+        self.heap.push((key, priority));
+        let position = self.heap.len() - 1;
+        self.positions[key] = Some(position);
+        self.sift_up(position);
+        None
+    }
+
+    /// Moves `key` towards the top by giving it a priority that ranks at least as high as its
+    /// current one (so a smaller priority, for a min-heap).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` isn't in the heap, or `priority` ranks lower than its current priority.
+    pub fn decrease_key(&mut self, key: usize, priority: P) {
+        let position = self
+            .position(key)
+            .unwrap_or_else(|| panic!("key {key} isn't in the heap"));
+        assert!(
+            !O::outranks(&self.heap[position].1, &priority),
+            "decrease_key would move key {key} down the heap"
+        );
+        self.heap[position].1 = priority;
+        self.sift_up(position);
+    }
+
+    /// Removes and returns the key with the highest-ranking priority.
+    pub fn pop(&mut self) -> Option<(usize, P)> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.remove_at(0))
+    }
+
+    pub fn peek(&self) -> Option<(usize, &P)> {
+        self.heap.first().map(|(key, priority)| (*key, priority))
+    }
+
+    /// Takes `key` out of the heap, wherever it is, returning its priority.
+    pub fn remove(&mut self, key: usize) -> Option<P> {
+        let position = self.position(key)?;
+        Some(self.remove_at(position).1)
+    }
+
+    fn position(&self, key: usize) -> Option<usize> {
+        self.positions.get(key).copied().flatten()
+    }
+
+    fn change_priority_at(&mut self, position: usize, priority: P) -> P {
+        let old = core::mem::replace(&mut self.heap[position].1, priority);
+        // it only needs to go one way, and the other does nothing
+        self.sift_up(position);
+        self.sift_down(position);
+        old
+    }
+
+    fn remove_at(&mut self, position: usize) -> (usize, P) {
+        let last = self.heap.len() - 1;
+        self.swap(position, last);
+        let (key, priority) = self.heap.pop().unwrap();
+        self.positions[key] = None;
+
+        if position < self.heap.len() {
+            // whatever was last could belong above or below here
+            self.sift_up(position);
+            self.sift_down(position);
+        }
+        (key, priority)
+    }
+
+    /// Swaps two heap slots and keeps `positions` pointing at them.
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a].0] = Some(a);
+        self.positions[self.heap[b].0] = Some(b);
+    }
+
+    fn sift_up(&mut self, mut index: usize) -> usize {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if !O::outranks(&self.heap[index].1, &self.heap[parent].1) {
+                break;
+            }
+            self.swap(index, parent);
+            index = parent;
+        }
+        index
+    }
+
+    fn sift_down(&mut self, mut index: usize) {
+        let end = self.heap.len();
+        loop {
+            let left = 2 * index + 1;
+            if left >= end {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < end && O::outranks(&self.heap[right].1, &self.heap[left].1) {
+                right
+            } else {
+                left
+            };
+            if !O::outranks(&self.heap[child].1, &self.heap[index].1) {
+                break;
+            }
+            self.swap(index, child);
+            index = child;
+        }
+    }
+}
+
+impl<P: Ord> Default for MyIndexedHeap<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: fmt::Debug, O: HeapOrder> fmt::Debug for MyIndexedHeap<P, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.heap.iter().map(|(key, priority)| (key, priority)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::cmp::{Ordering, Reverse};
+
+    use super::*;
+    use crate::test_utils::assert_panics;
+
+    fn pop_all<T: Ord, O: HeapOrder>(mut heap: MyBinaryHeap<T, O>) -> Vec<T> {
+        std::iter::from_fn(|| heap.pop()).collect()
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut heap = MyBinaryHeap::new();
+        assert_eq!(heap.pop(), None);
+        assert_eq!(heap.peek(), None);
+        for x in [5, 1, 8, 3, 9, 2, 8] {
+            heap.push(x);
+        }
+        assert_eq!(heap.peek(), Some(&9));
+        assert_eq!(heap.len(), 7);
+        assert_eq!(pop_all(heap), [9, 8, 8, 5, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_min_heap() {
+        let mut min = MyBinaryHeap::new_min();
+        min.extend([5, 1, 8, 3]);
+        assert_eq!(min.peek(), Some(&1));
+        assert_eq!(min.clone().into_sorted_vec(), [8, 5, 3, 1]);
+        assert_eq!(pop_all(min), [1, 3, 5, 8]);
+
+        let reversed: MyBinaryHeap<_> = [5, 1, 8, 3].into_iter().map(Reverse).collect();
+        assert_eq!(
+            pop_all(reversed),
+            [Reverse(1), Reverse(3), Reverse(5), Reverse(8)]
+        );
+    }
+
+    #[test]
+    fn test_peek_mut() {
+        let mut heap = MyBinaryHeap::from([1, 5, 3]);
+        assert_eq!(heap.peek_mut().as_deref(), Some(&5));
+        *heap.peek_mut().unwrap() = 0;
+        assert_eq!(heap.peek(), Some(&3));
+
+        let top = heap.peek_mut().unwrap();
+        assert_eq!(PeekMut::pop(top), 3);
+        assert_eq!(pop_all(heap), [1, 0]);
+        assert!(MyBinaryHeap::<i32>::new().peek_mut().is_none());
+    }
+
+    #[test]
+    fn test_into_sorted_vec() {
+        let heap: MyBinaryHeap<_> = [4, 9, 1, 7, 7, 0].into_iter().collect();
+        assert_eq!(heap.into_sorted_vec(), [0, 1, 4, 7, 7, 9]);
+        assert!(MyBinaryHeap::<i32>::new().into_sorted_vec().is_empty());
+    }
+
+    thread_local! {
+        static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Counts how many times it gets compared.
+    #[derive(PartialEq, Eq)]
+    struct Counted(u64);
+
+    impl PartialOrd for Counted {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Counted {
+        fn cmp(&self, other: &Self) -> Ordering {
+            COMPARISONS.set(COMPARISONS.get() + 1);
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn test_heapify_is_linear() {
+        // a scrambled but deterministic order
+        let n = 10_000;
+        let values: MyVec<_> = (0..n).map(|i| Counted(i * 7919 % n)).collect();
+
+        COMPARISONS.set(0);
+        let heap = MyBinaryHeap::from(values);
+        // bottom-up heapify makes fewer than 2 comparisons per element
+        assert!(
+            COMPARISONS.get() < 2 * n as usize,
+            "{} comparisons",
+            COMPARISONS.get()
+        );
+
+        let sorted = heap.into_sorted_vec();
+        assert!(sorted.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    }
+
+    #[test]
+    fn test_panicking_comparison_keeps_elements() {
+        #[derive(PartialEq, Eq, Debug)]
+        struct Fragile(i32);
+
+        impl PartialOrd for Fragile {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for Fragile {
+            fn cmp(&self, other: &Self) -> Ordering {
+                if self.0 == 13 || other.0 == 13 {
+                    panic!("unlucky");
+                }
+                self.0.cmp(&other.0)
+            }
+        }
+
+        let mut heap = MyBinaryHeap::from_iter((0..10).map(Fragile));
+        assert_panics(|| heap.push(Fragile(13)));
+        let mut values: Vec<_> = heap.into_vec().into_iter().map(|f| f.0).collect();
+        values.sort();
+        assert_eq!(values, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 13]);
+    }
+
+    #[test]
+    fn test_indexed_heap() {
+        let mut heap = MyIndexedHeap::new();
+        assert_eq!(heap.push(3, 30), None);
+        assert_eq!(heap.push(1, 10), None);
+        assert_eq!(heap.push(7, 70), None);
+        assert_eq!(heap.push(5, 50), None);
+        assert_eq!(heap.peek(), Some((1, &10)));
+
+        heap.decrease_key(7, 5);
+        assert_eq!(heap.peek(), Some((7, &5)));
+        // `push` on a key that's there changes its priority either way
+        assert_eq!(heap.push(7, 100), Some(5));
+        assert_eq!(heap.priority(7), Some(&100));
+
+        assert_eq!(heap.remove(3), Some(30));
+        assert_eq!(heap.remove(3), None);
+        assert!(!heap.contains(3));
+
+        assert_panics(|| MyIndexedHeap::<i32>::new().decrease_key(0, 1));
+        let mut heap_clone = MyIndexedHeap::new();
+        heap_clone.push(0, 1);
+        assert_panics(move || heap_clone.decrease_key(0, 2));
+
+        let order: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+        assert_eq!(order, [(1, 10), (5, 50), (7, 100)]);
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        // (from, to, weight)
+        let edges = [
+            (0, 1, 7),
+            (0, 2, 9),
+            (0, 5, 14),
+            (1, 2, 10),
+            (1, 3, 15),
+            (2, 3, 11),
+            (2, 5, 2),
+            (3, 4, 6),
+            (4, 5, 9),
+        ];
+        let n = 6;
+        let neighbours = |node: usize| {
+            edges.iter().filter_map(move |&(a, b, weight)| match node {
+                _ if node == a => Some((b, weight)),
+                _ if node == b => Some((a, weight)),
+                _ => None,
+            })
+        };
+
+        let mut distances = vec![u32::MAX; n];
+        distances[0] = 0;
+        let mut heap = MyIndexedHeap::new();
+        heap.push(0, 0);
+        while let Some((node, distance)) = heap.pop() {
+            for (next, weight) in neighbours(node) {
+                let through = distance + weight;
+                if through < distances[next] {
+                    if heap.contains(next) {
+                        heap.decrease_key(next, through);
+                    } else {
+                        heap.push(next, through);
+                    }
+                    distances[next] = through;
+                }
+            }
+        }
+        assert_eq!(distances, [0, 7, 9, 20, 20, 11]);
+    }
+}
@@ -0,0 +1,550 @@
+//! A vector of bits, packed 64 to a word.
+
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, Not};
+
+use crate::MyVec;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A growable vector of bits, like a `MyVec<bool>` that takes one bit per entry instead of eight.
+///
+/// Bit `i` is bit `i % 64` (counting from the least significant) of word `i / 64`. The bits past
+/// `len` in the last word are always zero, so counting, comparing and the bitwise operators can
+/// work on whole words without masking.
+///
+/// ```
+/// use wk9_01_implementing_vec::MyBitVec;
+///
+/// let a: MyBitVec = [true, true, false, false].into_iter().collect();
+/// let b: MyBitVec = [true, false, true, false].into_iter().collect();
+///
+/// assert_eq!(format!("{:?}", &a & &b), "[1000]");
+/// assert_eq!(format!("{:?}", &a | &b), "[1110]");
+/// assert_eq!(format!("{:?}", &a ^ &b), "[0110]");
+/// assert_eq!(format!("{:?}", !&b), "[0101]");
+///
+/// // or in place
+/// let mut c = a;
+/// c &= &b;
+/// assert_eq!(format!("{c:?}"), "[1000]");
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct MyBitVec {
+    words: MyVec<u64>,
+    len: usize,
+}
+
+impl MyBitVec {
+    pub fn new() -> Self {
+        Self {
+            words: MyVec::new(),
+            len: 0,
+        }
+    }
+
+    /// Creates an empty bit vector with room for at least `bits` bits.
+    pub fn with_capacity(bits: usize) -> Self {
+        Self {
+            words: MyVec::with_capacity(bits.div_ceil(WORD_BITS)),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.push(0);
+        }
+        self.len += 1;
+        if bit {
+            self.set(self.len - 1, true);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<bool> {
+        let index = self.len.checked_sub(1)?;
+        let bit = self.get(index)?;
+        // keep the bits past `len` zero
+        self.set(index, false);
+        self.len = index;
+        if index.is_multiple_of(WORD_BITS) {
+            self.words.pop();
+        }
+        Some(bit)
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index >= self.len {
+            return None;
+        }
+        Some(self.words[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, bit: bool) {
+        let word = self.checked_word(index);
+        let word = &mut self.words[word];
+        let mask = 1 << (index % WORD_BITS);
+        if bit {
+            *word |= mask;
+        } else {
+            *word &= !mask;
+        }
+    }
+
+    /// Flips the bit at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn toggle(&mut self, index: usize) {
+        let word = self.checked_word(index);
+        self.words[word] ^= 1 << (index % WORD_BITS);
+    }
+
+    /// Which word `index` is in, after checking it's in bounds.
+    fn checked_word(&self, index: usize) -> usize {
+        let len = self.len;
+        assert!(
+            index < len,
+            "index out of bounds: the len is {len} but the index is {index}"
+        );
+        index / WORD_BITS
+    }
+
+    pub fn clear(&mut self) {
+        self.words.clear();
+        self.len = 0;
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// How many ones there are before `index`. O(n / 64).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn rank(&self, index: usize) -> usize {
+        let len = self.len;
+        assert!(
+            index <= len,
+            "rank index (is {index}) should be <= len (is {len})"
+        );
+        let (full, rest) = (index / WORD_BITS, index % WORD_BITS);
+        let ones: usize = self.words[..full]
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum();
+        if rest == 0 {
+            ones
+        } else {
+            ones + (self.words[full] & ((1 << rest) - 1)).count_ones() as usize
+        }
+    }
+
+    /// Where the `n`th one (counting from zero) is, or `None` if there aren't that many. The
+    /// opposite of [`rank`](MyBitVec::rank): `rank(select(n)) == n`. O(n / 64).
+    pub fn select(&self, mut n: usize) -> Option<usize> {
+        for (i, &word) in self.words.iter().enumerate() {
+            let ones = word.count_ones() as usize;
+            if n < ones {
+                // clear the lowest `n` ones and the one we want is the lowest left
+                let mut word = word;
+                for _ in 0..n {
+                    word &= word - 1;
+                }
+                return Some(i * WORD_BITS + word.trailing_zeros() as usize);
+            }
+            n -= ones;
+        }
+        None
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            bits: self,
+            index: 0,
+        }
+    }
+
+    /// The indices of the ones, in order. Skips over the zeros a word at a time, so it's quick
+    /// when they're sparse.
+    pub fn iter_ones(&self) -> IterOnes<'_> {
+        IterOnes {
+            words: &self.words,
+            next_word: 0,
+            current: 0,
+        }
+    }
+
+    /// The words the bits are packed into, with the bits past `len` zero.
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Zeroes the bits in the last word past `len`, after something that might have set them.
+    fn clear_tail(&mut self) {
+        let rest = self.len % WORD_BITS;
+        if rest != 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << rest) - 1;
+            }
+        }
+    }
+
+    /// Combines `other` into this word by word, for the bitwise operators.
+    fn zip_words(&mut self, other: &Self, op: impl Fn(u64, u64) -> u64) {
+        assert_eq!(self.len, other.len, "bit vectors have different lengths");
+        for (word, &other) in self.words.iter_mut().zip(other.words.iter()) {
+            *word = op(*word, other);
+        }
+    }
+}
+
+// Each operator works in place through its `*Assign` form, and the rest are built on that: by
+// value reuses the left-hand side's words, and by reference copies them once for the result.
+macro_rules! impl_bit_op {
+    ($($op:ident, $method:ident, $assign:ident, $assign_method:ident, $combine:tt;)*) => {
+        $(
+            impl $assign<&MyBitVec> for MyBitVec {
+                /// # Panics
+                ///
+                /// Panics if the lengths are different.
+                fn $assign_method(&mut self, other: &MyBitVec) {
+                    self.zip_words(other, |a, b| a $combine b);
+                }
+            }
+
+            impl $assign for MyBitVec {
+                fn $assign_method(&mut self, other: MyBitVec) {
+                    self.$assign_method(&other);
+                }
+            }
+
+            impl $op for MyBitVec {
+                type Output = Self;
+
+                fn $method(mut self, other: Self) -> Self::Output {
+                    self.$assign_method(&other);
+                    self
+                }
+            }
+
+            impl $op<&MyBitVec> for &MyBitVec {
+                type Output = MyBitVec;
+
+                fn $method(self, other: &MyBitVec) -> Self::Output {
+                    let mut result = self.clone();
+                    result.$assign_method(other);
+                    result
+                }
+            }
+        )*
+    };
+}
+
+impl_bit_op! {
+    BitAnd, bitand, BitAndAssign, bitand_assign, &;
+    BitOr, bitor, BitOrAssign, bitor_assign, |;
+    BitXor, bitxor, BitXorAssign, bitxor_assign, ^;
+}
+
+impl Not for MyBitVec {
+    type Output = Self;
+
+    fn not(mut self) -> Self::Output {
+        for word in self.words.iter_mut() {
+            *word = !*word;
+        }
+        self.clear_tail();
+        self
+    }
+}
+
+impl Not for &MyBitVec {
+    type Output = MyBitVec;
+
+    fn not(self) -> Self::Output {
+        !self.clone()
+    }
+}
+
+impl Index<usize> for MyBitVec {
+    type Output = bool;
+
+    fn index(&self, index: usize) -> &bool {
+        // there's no `bool` in the words to borrow, so hand out one of the two constants
+        let word = self.checked_word(index);
+        if self.words[word] & (1 << (index % WORD_BITS)) != 0 {
+            &true
+        } else {
+            &false
+        }
+    }
+}
+
+impl fmt::Debug for MyBitVec {
+    /// The bits as 1s and 0s, first to last.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        for bit in self.iter() {
+            f.write_str(if bit { "1" } else { "0" })?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl Extend<bool> for MyBitVec {
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        for bit in iter {
+            self.push(bit);
+        }
+    }
+}
+
+impl FromIterator<bool> for MyBitVec {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bits = MyBitVec::new();
+        bits.extend(iter);
+        bits
+    }
+}
+
+impl From<MyVec<bool>> for MyBitVec {
+    fn from(vec: MyVec<bool>) -> Self {
+        let mut bits = MyBitVec::with_capacity(vec.len());
+        bits.extend(vec.iter().copied());
+        bits
+    }
+}
+
+impl From<&MyBitVec> for MyVec<bool> {
+    fn from(bits: &MyBitVec) -> Self {
+        let mut vec = MyVec::with_capacity(bits.len());
+        vec.extend(bits.iter());
+        vec
+    }
+}
+
+impl From<MyBitVec> for MyVec<bool> {
+    fn from(bits: MyBitVec) -> Self {
+        MyVec::from(&bits)
+    }
+}
+
+impl<'a> IntoIterator for &'a MyBitVec {
+    type Item = bool;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the bits of a [`MyBitVec`], as `bool`s.
+pub struct Iter<'a> {
+    bits: &'a MyBitVec,
+    index: usize,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        let bit = self.bits.get(self.index)?;
+        self.index += 1;
+        Some(bit)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.bits.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+impl FusedIterator for Iter<'_> {}
+
+/// Iterator over the indices of the ones in a [`MyBitVec`], from [`MyBitVec::iter_ones`].
+pub struct IterOnes<'a> {
+    words: &'a [u64],
+    // the word after the current one
+    next_word: usize,
+    // what's left of the current word, with the ones already returned cleared
+    current: u64,
+}
+
+impl Iterator for IterOnes<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.current = *self.words.get(self.next_word)?;
+            self.next_word += 1;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        // clear the lowest one
+        self.current &= self.current - 1;
+        Some((self.next_word - 1) * WORD_BITS + bit)
+    }
+}
+
+impl FusedIterator for IterOnes<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::assert_panics;
+
+    /// A deterministic mix of ones and zeros that's not periodic in 64.
+    fn pattern(len: usize) -> Vec<bool> {
+        (0..len).map(|i| (i * i + i / 3) % 7 < 3).collect()
+    }
+
+    #[test]
+    fn test_push_pop_get_set() {
+        let mut expected = pattern(200);
+        let mut bits: MyBitVec = expected.iter().copied().collect();
+        assert_eq!(bits.len(), 200);
+        assert_eq!(bits.as_words().len(), 4);
+        assert!((0..200).all(|i| bits.get(i) == Some(expected[i]) && bits[i] == expected[i]));
+        assert_eq!(bits.get(200), None);
+
+        bits.set(130, true);
+        bits.set(131, false);
+        bits.toggle(132);
+        assert_eq!(bits.get(130), Some(true));
+        assert_eq!(bits.get(131), Some(false));
+        assert_eq!(bits.get(132), Some(!expected[132]));
+        expected[130] = true;
+        expected[131] = false;
+        expected[132] = !expected[132];
+        assert_panics(|| MyBitVec::new().set(0, true));
+        assert_panics(|| bits.clone().toggle(200));
+
+        for i in (0..200).rev() {
+            assert_eq!(bits.pop(), Some(expected[i]));
+            assert_eq!(bits.len(), i);
+            assert_eq!(bits.as_words().len(), i.div_ceil(64));
+        }
+        assert_eq!(bits.pop(), None);
+    }
+
+    #[test]
+    fn test_bits_past_len_stay_zero() {
+        let mut bits: MyBitVec = [true; 70].into_iter().collect();
+        bits.pop();
+        bits.pop();
+        assert_eq!(bits.as_words(), [u64::MAX, 0b1111]);
+        assert_eq!(bits.count_ones(), 68);
+
+        let flipped = !bits.clone();
+        assert_eq!(flipped.as_words(), [0, 0]);
+        assert_eq!(flipped.count_zeros(), 68);
+
+        // so equal bits compare equal, however they got there
+        let mut other: MyBitVec = [true; 68].into_iter().collect();
+        assert_eq!(bits, other);
+        other.push(true);
+        other.pop();
+        assert_eq!(bits, other);
+    }
+
+    #[test]
+    fn test_bitwise_ops_match_bools() {
+        let a_bools = pattern(150);
+        let b_bools: Vec<bool> = (0..150).map(|i| i % 5 == 0).collect();
+        let a: MyBitVec = a_bools.iter().copied().collect();
+        let b: MyBitVec = b_bools.iter().copied().collect();
+
+        let check = |bits: MyBitVec, op: fn(bool, bool) -> bool| {
+            let expected: Vec<bool> = (0..150).map(|i| op(a_bools[i], b_bools[i])).collect();
+            assert!(bits.iter().eq(expected));
+        };
+        check(&a & &b, |x, y| x & y);
+        check(&a | &b, |x, y| x | y);
+        check(&a ^ &b, |x, y| x ^ y);
+        check(!&a, |x, _| !x);
+        assert_eq!(!!&a, a);
+
+        // the in-place and by-value forms agree with the by-reference ones
+        let mut c = a.clone();
+        c |= &b;
+        assert_eq!(c, &a | &b);
+        c ^= b.clone();
+        assert_eq!(c, &(&a | &b) ^ &b);
+        c &= &a;
+        assert_eq!(c, a.clone() & (&(&a | &b) ^ &b));
+
+        let shorter: MyBitVec = pattern(149).into_iter().collect();
+        assert_panics(|| &a & &shorter);
+        assert_panics(|| {
+            let mut c = a.clone();
+            c ^= &shorter;
+        });
+    }
+
+    #[test]
+    fn test_iter_ones() {
+        let bools = pattern(300);
+        let bits: MyBitVec = bools.iter().copied().collect();
+        let expected: Vec<usize> = (0..300).filter(|&i| bools[i]).collect();
+        assert!(bits.iter_ones().eq(expected.iter().copied()));
+        assert_eq!(bits.count_ones(), expected.len());
+
+        // whole words of zeros get skipped over
+        let mut sparse = MyBitVec::new();
+        sparse.extend((0..1000).map(|i| i == 3 || i == 640 || i == 999));
+        assert!(sparse.iter_ones().eq([3, 640, 999]));
+        assert_eq!(MyBitVec::new().iter_ones().next(), None);
+    }
+
+    #[test]
+    fn test_rank_select() {
+        let bools = pattern(500);
+        let bits: MyBitVec = bools.iter().copied().collect();
+        let mut ones = 0;
+        for (i, &bit) in bools.iter().enumerate() {
+            assert_eq!(bits.rank(i), ones, "rank({i})");
+            if bit {
+                assert_eq!(bits.select(ones), Some(i), "select({ones})");
+                ones += 1;
+            }
+        }
+        assert_eq!(bits.rank(500), ones);
+        assert_eq!(bits.select(ones), None);
+        assert_panics(|| bits.rank(501));
+    }
+
+    #[test]
+    fn test_my_vec_bool_round_trip() {
+        let mut vec = MyVec::new();
+        for bit in pattern(130) {
+            vec.push(bit);
+        }
+        let bits = MyBitVec::from(vec.clone());
+        assert_eq!(MyVec::from(&bits), vec);
+        assert_eq!(MyVec::from(bits), vec);
+        assert_eq!(MyVec::<bool>::from(MyBitVec::new()).len(), 0);
+    }
+}
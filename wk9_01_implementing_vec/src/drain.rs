@@ -0,0 +1,357 @@
+use core::iter::{self, FusedIterator};
+
+use crate::{Doubling, Global, GrowthPolicy, MyAllocator, MyVec};
+
+/// Draining iterator over part of a [`MyVec`], created by [`MyVec::drain`].
+///
+/// While the `Drain` is alive the vector's length only covers the elements *before* the drained
+/// range, so leaking the `Drain` (e.g. with `mem::forget`) leaks elements but never double drops them.
+pub struct Drain<'a, T, A: MyAllocator = Global, G: GrowthPolicy = Doubling> {
+    vec: &'a mut MyVec<T, A, G>,
+    // elements in `start..end` haven't been yielded yet
+    start: usize,
+    end: usize,
+    // where the elements after the drained range live, until we move them back down
+    tail_start: usize,
+    tail_len: usize,
+}
+
+impl<'a, T, A: MyAllocator, G: GrowthPolicy> Drain<'a, T, A, G> {
+    pub(crate) fn new(vec: &'a mut MyVec<T, A, G>, start: usize, end: usize) -> Self {
+        let len = vec.size;
+        vec.size = start;
+
+        Self {
+            vec,
+            start,
+            end,
+            tail_start: end,
+            tail_len: len - end,
+        }
+    }
+
+    /// Returns the elements that haven't been yielded yet.
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: `start..end` is initialised and hasn't been moved out of yet
+        unsafe {
+            core::slice::from_raw_parts(self.vec.ptr_to_elem(self.start), self.end - self.start)
+        }
+    }
+
+    /// Moves the tail down so it sits straight after the vector's current length.
+    fn move_tail_back(&mut self) {
+        let vec = &mut *self.vec;
+        if self.tail_len > 0 {
+            if self.tail_start != vec.size {
+                // SAFETY: both ranges are within capacity, and everything between `vec.size`
+                //         and `tail_start` has been moved out or dropped
+                unsafe {
+                    core::ptr::copy(
+                        vec.ptr_to_elem(self.tail_start),
+                        vec.ptr_to_elem(vec.size),
+                        self.tail_len,
+                    );
+                }
+                self.tail_start = vec.size;
+            }
+            vec.size += self.tail_len;
+            self.tail_len = 0;
+        }
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> Iterator for Drain<'_, T, A, G> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            // SAFETY: `start < end`, and this element hasn't been yielded yet
+            let value = unsafe { self.vec.ptr_to_elem(self.start).read() };
+            self.start += 1;
+            Some(value)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> DoubleEndedIterator for Drain<'_, T, A, G> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            self.end -= 1;
+            // SAFETY: same as `next`
+            Some(unsafe { self.vec.ptr_to_elem(self.end).read() })
+        }
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> ExactSizeIterator for Drain<'_, T, A, G> {}
+impl<T, A: MyAllocator, G: GrowthPolicy> FusedIterator for Drain<'_, T, A, G> {}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> Drop for Drain<'_, T, A, G> {
+    fn drop(&mut self) {
+        // even if one of the leftover elements panics while dropping, the tail still has to come back
+        struct MoveTailOnDrop<'r, 'a, T, A: MyAllocator, G: GrowthPolicy>(
+            &'r mut Drain<'a, T, A, G>,
+        );
+
+        impl<T, A: MyAllocator, G: GrowthPolicy> Drop for MoveTailOnDrop<'_, '_, T, A, G> {
+            fn drop(&mut self) {
+                self.0.move_tail_back();
+            }
+        }
+
+        let guard = MoveTailOnDrop(self);
+        let drain = &mut *guard.0;
+
+        let remaining = drain.end - drain.start;
+        // SAFETY: `start..end` is initialised and not yet yielded. We mark it as taken before
+        //         dropping, and dropping a slice in place keeps going past a panicking element
+        unsafe {
+            let remaining =
+                core::ptr::slice_from_raw_parts_mut(drain.vec.ptr_to_elem(drain.start), remaining);
+            drain.start = drain.end;
+            remaining.drop_in_place();
+        }
+    }
+}
+
+/// Splicing iterator for a [`MyVec`], created by [`MyVec::splice`].
+///
+/// Yields the removed elements, then puts `replace_with` in their place when dropped.
+pub struct Splice<'a, I: Iterator, A: MyAllocator = Global, G: GrowthPolicy = Doubling> {
+    drain: Drain<'a, I::Item, A, G>,
+    replace_with: I,
+}
+
+impl<'a, I: Iterator, A: MyAllocator, G: GrowthPolicy> Splice<'a, I, A, G> {
+    pub(crate) fn new(drain: Drain<'a, I::Item, A, G>, replace_with: I) -> Self {
+        Self {
+            drain,
+            replace_with,
+        }
+    }
+}
+
+impl<I: Iterator, A: MyAllocator, G: GrowthPolicy> Iterator for Splice<'_, I, A, G> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        self.drain.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drain.size_hint()
+    }
+}
+
+impl<I: Iterator, A: MyAllocator, G: GrowthPolicy> DoubleEndedIterator for Splice<'_, I, A, G> {
+    fn next_back(&mut self) -> Option<I::Item> {
+        self.drain.next_back()
+    }
+}
+
+impl<I: Iterator, A: MyAllocator, G: GrowthPolicy> ExactSizeIterator for Splice<'_, I, A, G> {}
+impl<I: Iterator, A: MyAllocator, G: GrowthPolicy> FusedIterator for Splice<'_, I, A, G> {}
+
+impl<I: Iterator, A: MyAllocator, G: GrowthPolicy> Drop for Splice<'_, I, A, G> {
+    fn drop(&mut self) {
+        // get rid of anything the caller didn't take out of the drained range
+        self.drain.by_ref().for_each(drop);
+
+        // From here on, `vec.size` is always the end of what's been written into the gap,
+        // so if `replace_with` panics the `Drain` destructor still moves the tail back correctly.
+        if self.drain.tail_len == 0 {
+            for value in self.replace_with.by_ref() {
+                self.drain.vec.push(value);
+            }
+            return;
+        }
+
+        if !Self::fill(&mut self.drain, &mut self.replace_with) {
+            return;
+        }
+
+        // There's more to come than fits in the gap. Collecting it somewhere first would mean
+        // allocating outside the vector's allocator, so move the tail further back instead, by
+        // at least twice as much each time so a long iterator only moves it O(log n) times.
+        let mut additional = 0;
+        while let Some(value) = self.replace_with.next() {
+            additional = (additional * 2).max(self.replace_with.size_hint().0 + 1);
+            self.move_tail(additional);
+            let mut values = iter::once(value).chain(self.replace_with.by_ref());
+            if !Self::fill(&mut self.drain, &mut values) {
+                break;
+            }
+        }
+
+        // `Drain`'s destructor moves the tail back into place, closing up any of the gap that
+        // didn't get used
+    }
+}
+
+impl<I: Iterator, A: MyAllocator, G: GrowthPolicy> Splice<'_, I, A, G> {
+    /// Fills the gap between the vector's length and the tail from `values`.
+    /// Returns `false` if it ran out of values first.
+    fn fill(
+        drain: &mut Drain<'_, I::Item, A, G>,
+        values: &mut impl Iterator<Item = I::Item>,
+    ) -> bool {
+        while drain.vec.size < drain.tail_start {
+            let Some(value) = values.next() else {
+                return false;
+            };
+
+            // SAFETY: the slot is in the gap, so it's inside capacity and currently uninitialised
+            unsafe { drain.vec.ptr_to_elem(drain.vec.size).write(value) };
+            drain.vec.size += 1;
+        }
+        true
+    }
+
+    /// Moves the tail `additional` slots further back, growing the buffer if needed.
+    fn move_tail(&mut self, additional: usize) {
+        let vec = &mut *self.drain.vec;
+        let used = self.drain.tail_start + self.drain.tail_len;
+        vec.reserve(used + additional - vec.size);
+
+        let new_tail_start = self.drain.tail_start + additional;
+        // SAFETY: we just reserved room for the tail at its new position
+        unsafe {
+            core::ptr::copy(
+                vec.ptr_to_elem(self.drain.tail_start),
+                vec.ptr_to_elem(new_tail_start),
+                self.drain.tail_len,
+            );
+        }
+        self.drain.tail_start = new_tail_start;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_panics, from_slice, tracked, values, DropCounter};
+
+    #[test]
+    fn test_drain() {
+        let mut vec = from_slice(&[1, 2, 3, 4, 5, 6]);
+        assert_eq!(vec.drain(1..3).collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(vec[..], [1, 4, 5, 6]);
+
+        let mut drain = vec.drain(1..);
+        assert_eq!(drain.len(), 3);
+        assert_eq!(drain.next_back(), Some(6));
+        assert_eq!(drain.as_slice(), [4, 5]);
+        drop(drain);
+        assert_eq!(vec[..], [1]);
+
+        assert_eq!(vec.drain(..).collect::<Vec<_>>(), [1]);
+        assert!(vec.is_empty());
+        assert_eq!(vec.drain(..).next(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 4 out of range for slice of length 3")]
+    fn test_drain_out_of_bounds() {
+        from_slice(&[1, 2, 3]).drain(1..=3);
+    }
+
+    #[test]
+    fn test_drain_drops_unyielded() {
+        let counter = DropCounter::new();
+        let mut vec = tracked(&counter, &[0, 1, 2, 3, 4, 5, 6, 7]);
+
+        let mut drain = vec.drain(2..6);
+        assert_eq!(drain.next().map(|x| x.value), Some(2));
+        assert_eq!(counter.drops(), 1);
+        drop(drain);
+
+        assert_eq!(counter.drops(), 4);
+        assert_eq!(values(&vec), [0, 1, 6, 7]);
+        drop(vec);
+        assert_eq!(counter.drops(), 8);
+    }
+
+    #[test]
+    fn test_drain_panicking_drop() {
+        let counter = DropCounter::new();
+        let mut vec = MyVec::new();
+        vec.push(counter.track(0));
+        vec.push(counter.track_panicking(1));
+        vec.push(counter.track(2));
+        vec.push(counter.track(3));
+
+        assert_panics(|| drop(vec.drain(..3)));
+
+        // every drained element was still dropped once, and the tail made it back
+        assert_eq!(counter.drops(), 3);
+        assert_eq!(values(&vec), [3]);
+        drop(vec);
+        assert_eq!(counter.drops(), 4);
+    }
+
+    #[test]
+    fn test_drain_forget() {
+        let mut vec = from_slice(&[1, 2, 3, 4]);
+        std::mem::forget(vec.drain(1..3));
+        // leaking the drain leaks the tail too, but nothing is dropped twice
+        assert_eq!(vec[..], [1]);
+    }
+
+    #[test]
+    fn test_splice() {
+        // same length
+        let mut vec = from_slice(&[1, 2, 3, 4]);
+        let removed: Vec<_> = vec.splice(1..3, [20, 30]).collect();
+        assert_eq!(removed, [2, 3]);
+        assert_eq!(vec[..], [1, 20, 30, 4]);
+
+        // shorter
+        vec.splice(1..3, [0]);
+        assert_eq!(vec[..], [1, 0, 4]);
+
+        // longer, with the tail having to move past the current capacity
+        vec.splice(1..2, 10..20);
+        assert_eq!(vec[..4], [1, 10, 11, 12]);
+        assert_eq!(vec.len(), 12);
+        assert_eq!(vec[11], 4);
+
+        // at the end
+        vec.splice(11.., [5, 6]);
+        assert_eq!(vec[10..], [19, 5, 6]);
+
+        // empty range is an insertion
+        vec.splice(0..0, [-1]);
+        assert_eq!(vec[..2], [-1, 1]);
+    }
+
+    #[test]
+    fn test_splice_panicking_iterator() {
+        let counter = DropCounter::new();
+        let mut vec = tracked(&counter, &[0, 1, 2, 3]);
+
+        let replacements = [10, 11, 12, 13].map(|i| counter.track(i));
+        let replace_with = replacements.into_iter().inspect(|x| {
+            if x.value == 12 {
+                panic!("iterator panicked");
+            }
+        });
+
+        assert_panics(|| drop(vec.splice(1..2, replace_with)));
+
+        // 10 went into the gap left by 1 and 11 into the room made by moving the tail, then 12
+        // panicked, so it's dropped along with 13 and the gap closes up again
+        assert_eq!(values(&vec), [0, 10, 11, 2, 3]);
+        assert_eq!(counter.drops(), 3);
+        drop(vec);
+        assert_eq!(counter.drops(), 8);
+    }
+}
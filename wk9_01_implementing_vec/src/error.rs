@@ -0,0 +1,112 @@
+use core::alloc::Layout;
+use core::fmt;
+use core::str::Utf8Error;
+
+use crate::MyVec;
+
+/// The error returned by the `try_*` allocation methods on [`MyVec`](crate::MyVec).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TryReserveError {
+    kind: TryReserveErrorKind,
+}
+
+/// Why an allocation couldn't be made.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TryReserveErrorKind {
+    /// The requested capacity doesn't fit in a `usize`, or needs more than `isize::MAX` bytes.
+    CapacityOverflow,
+
+    /// The allocator returned null for this layout.
+    AllocError { layout: Layout },
+}
+
+impl TryReserveError {
+    pub fn kind(&self) -> TryReserveErrorKind {
+        self.kind.clone()
+    }
+}
+
+impl From<TryReserveErrorKind> for TryReserveError {
+    fn from(kind: TryReserveErrorKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")?;
+        match self.kind {
+            TryReserveErrorKind::CapacityOverflow => {
+                f.write_str(" because the computed capacity exceeded the collection's maximum")
+            }
+            TryReserveErrorKind::AllocError { layout } => write!(
+                f,
+                " because the allocator couldn't provide {} bytes aligned to {}",
+                layout.size(),
+                layout.align()
+            ),
+        }
+    }
+}
+
+impl core::error::Error for TryReserveError {}
+
+/// The error returned by [`MyString::from_utf8`](crate::MyString::from_utf8) when the bytes aren't
+/// valid UTF-8. It says where things went wrong, and hands the bytes back.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FromUtf8Error {
+    pub(crate) bytes: MyVec<u8>,
+    pub(crate) error: Utf8Error,
+}
+
+impl FromUtf8Error {
+    /// The bytes that were being converted.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Gives the bytes back, so they aren't lost.
+    pub fn into_bytes(self) -> MyVec<u8> {
+        self.bytes
+    }
+
+    /// How many bytes at the start are valid UTF-8.
+    pub fn valid_up_to(&self) -> usize {
+        self.error.valid_up_to()
+    }
+
+    /// How long the invalid sequence after `valid_up_to` is, or `None` if the bytes just end in
+    /// the middle of a character (so more bytes might make it valid).
+    pub fn error_len(&self) -> Option<usize> {
+        self.error.error_len()
+    }
+
+    /// The underlying error from `std`.
+    pub fn utf8_error(&self) -> Utf8Error {
+        self.error
+    }
+}
+
+impl fmt::Display for FromUtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start = self.valid_up_to();
+        match self.error_len() {
+            Some(len) => write!(
+                f,
+                "invalid utf-8 sequence {:02x?} of {len} bytes from index {start}",
+                &self.bytes[start..start + len]
+            ),
+            None => write!(
+                f,
+                "incomplete utf-8 byte sequence {:02x?} from index {start}",
+                &self.bytes[start..]
+            ),
+        }
+    }
+}
+
+impl core::error::Error for FromUtf8Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
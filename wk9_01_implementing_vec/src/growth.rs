@@ -0,0 +1,118 @@
+//! How much a buffer grows by when it runs out of room.
+
+/// Decides the new capacity when a `MyVec` (or anything else built on its buffer) has to grow.
+///
+/// Only consulted for amortised growth (`push`, `insert`, `reserve`, ...). `reserve_exact` and
+/// `with_capacity` always ask for exactly what they're given.
+pub trait GrowthPolicy {
+    /// The capacity to grow to from `capacity`, when the buffer needs room for at least `required`
+    /// elements of `elem_size` bytes each. Must return at least `required`.
+    ///
+    /// Never called for zero-sized types, which don't need to grow.
+    fn grow(capacity: usize, required: usize, elem_size: usize) -> usize;
+}
+
+/// Doubles the capacity, starting at 8. This is the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Doubling;
+
+impl GrowthPolicy for Doubling {
+    fn grow(capacity: usize, required: usize, _elem_size: usize) -> usize {
+        capacity.saturating_mul(2).max(required).max(8)
+    }
+}
+
+/// Grows by half the current capacity, starting at 4. Wastes less memory than [`Doubling`],
+/// at the cost of a few more reallocations.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OneAndAHalf;
+
+impl GrowthPolicy for OneAndAHalf {
+    fn grow(capacity: usize, required: usize, _elem_size: usize) -> usize {
+        capacity.saturating_add(capacity / 2).max(required).max(4)
+    }
+}
+
+/// Grows by `STEP` elements at a time. Never over-allocates by more than `STEP`, but pushing
+/// becomes O(n) amortised, so it's only a good idea when you know roughly how big things get.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixedIncrement<const STEP: usize = 64>;
+
+impl<const STEP: usize> GrowthPolicy for FixedIncrement<STEP> {
+    fn grow(capacity: usize, required: usize, _elem_size: usize) -> usize {
+        capacity.saturating_add(STEP).max(required)
+    }
+}
+
+/// Doubles, but rounds the buffer up to a whole number of pages, so the allocator (or `mmap`
+/// behind it) doesn't leave a partly used page at the end.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PageAligned;
+
+impl PageAligned {
+    pub const PAGE_SIZE: usize = 4096;
+}
+
+impl GrowthPolicy for PageAligned {
+    fn grow(capacity: usize, required: usize, elem_size: usize) -> usize {
+        // zero-sized types never grow, their capacity is already `usize::MAX`
+        debug_assert!(elem_size != 0);
+        let wanted = capacity.saturating_mul(2).max(required);
+
+        // if the byte count overflows, just ask for `wanted` and let the layout check complain
+        let Some(bytes) = wanted
+            .checked_mul(elem_size)
+            .and_then(|bytes| bytes.checked_next_multiple_of(Self::PAGE_SIZE))
+        else {
+            return wanted;
+        };
+        bytes / elem_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies() {
+        assert_eq!(Doubling::grow(0, 1, 4), 8);
+        assert_eq!(Doubling::grow(8, 9, 4), 16);
+        assert_eq!(Doubling::grow(8, 100, 4), 100);
+
+        assert_eq!(OneAndAHalf::grow(0, 1, 4), 4);
+        assert_eq!(OneAndAHalf::grow(4, 5, 4), 6);
+        assert_eq!(OneAndAHalf::grow(6, 7, 4), 9);
+
+        assert_eq!(FixedIncrement::<10>::grow(0, 1, 4), 10);
+        assert_eq!(FixedIncrement::<10>::grow(10, 11, 4), 20);
+        assert_eq!(FixedIncrement::<10>::grow(10, 50, 4), 50);
+
+        // a page of `u32`s is 1024 of them
+        assert_eq!(PageAligned::grow(0, 1, 4), 1024);
+        assert_eq!(PageAligned::grow(1024, 1025, 4), 2048);
+        assert_eq!(PageAligned::grow(0, 1, 500), 8);
+        assert_eq!(PageAligned::grow(0, 1, 1000), 4);
+        assert_eq!(PageAligned::grow(0, 1, 5000), 1);
+    }
+
+    #[test]
+    fn test_never_less_than_required() {
+        let grows: [fn(usize, usize, usize) -> usize; 4] = [
+            Doubling::grow,
+            OneAndAHalf::grow,
+            FixedIncrement::<3>::grow,
+            PageAligned::grow,
+        ];
+        for grow in grows {
+            for (capacity, required, elem_size) in [
+                (0, 1, 1),
+                (100, 1000, 24),
+                (usize::MAX - 1, usize::MAX, 1),
+                (usize::MAX / 2, usize::MAX / 2 + 1, 8),
+            ] {
+                assert!(grow(capacity, required, elem_size) >= required);
+            }
+        }
+    }
+}
@@ -0,0 +1,960 @@
+//! An open-addressing hash map on top of `MyVec`.
+
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+#[cfg(not(feature = "std"))]
+use core::hash::{BuildHasherDefault, Hasher};
+use core::iter::FusedIterator;
+use core::ops::Index;
+
+use crate::MyVec;
+
+/// What a [`MyHashMap`] hashes with unless it's given something else. With the `std` feature that's
+/// `HashMap`'s randomly seeded SipHash, otherwise `FnvHasher`, since there's nowhere to get a
+/// random seed from.
+#[cfg(feature = "std")]
+pub type DefaultHashBuilder = std::collections::hash_map::RandomState;
+
+/// What a [`MyHashMap`] hashes with unless it's given something else. With the `std` feature that's
+/// `HashMap`'s randomly seeded SipHash, otherwise [`FnvHasher`], since there's nowhere to get a
+/// random seed from.
+#[cfg(not(feature = "std"))]
+pub type DefaultHashBuilder = BuildHasherDefault<FnvHasher>;
+
+/// The 64-bit FNV-1a hash. Quick and simple, but it isn't seeded, so anyone who can pick the keys
+/// can make them all collide.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Copy, Debug)]
+pub struct FnvHasher(u64);
+
+#[cfg(not(feature = "std"))]
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A hash map, like `HashMap<K, V>`, using open addressing with Robin Hood probing.
+///
+/// Every entry lives directly in one big `MyVec` of slots. A key goes in the first free slot at or
+/// after its "home" slot (picked by its hash), but an entry that's further from home than the one in
+/// the way takes the richer entry's slot, and that entry keeps looking. This keeps every entry's
+/// distance from home about the same, so lookups stay short even when the table is quite full, and
+/// a lookup can stop as soon as it sees an entry that's closer to home than it would be.
+///
+/// Removing shifts the following entries back one slot instead of leaving a tombstone, so the table
+/// never fills up with dead entries.
+pub struct MyHashMap<K, V, S = DefaultHashBuilder> {
+    // always empty or a power of two long, so `hash & mask` picks a slot
+    slots: MyVec<Option<Bucket<K, V>>>,
+    len: usize,
+    max_load_factor: f64,
+    hash_builder: S,
+}
+
+struct Bucket<K, V> {
+    // saved so growing doesn't need to rehash, and most mismatches can skip comparing keys
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+impl<K, V> MyHashMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_hasher(DefaultHashBuilder::default())
+    }
+
+    /// Creates an empty map with room for at least `capacity` entries before it resizes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, DefaultHashBuilder::default())
+    }
+}
+
+impl<K, V, S> MyHashMap<K, V, S> {
+    pub const DEFAULT_MAX_LOAD_FACTOR: f64 = 0.875;
+
+    /// Creates an empty map that hashes keys with `hash_builder`.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self {
+            slots: MyVec::new(),
+            len: 0,
+            max_load_factor: Self::DEFAULT_MAX_LOAD_FACTOR,
+            hash_builder,
+        }
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let mut map = Self::with_hasher(hash_builder);
+        map.resize(map.slots_for(capacity));
+        map
+    }
+
+    /// Sets how full (as a fraction of the slots) the table can get before it grows, which is
+    /// 0.875 by default. Lower means shorter probes but more memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0.0 < max_load_factor <= 1.0`.
+    pub fn with_max_load_factor(mut self, max_load_factor: f64) -> Self {
+        assert!(
+            max_load_factor > 0.0 && max_load_factor <= 1.0,
+            "load factor (is {max_load_factor}) should be in (0, 1]"
+        );
+        self.max_load_factor = max_load_factor;
+        self
+    }
+
+    pub fn max_load_factor(&self) -> f64 {
+        self.max_load_factor
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// How many entries the map can hold before it resizes.
+    pub fn capacity(&self) -> usize {
+        (self.slots.len() as f64 * self.max_load_factor) as usize
+    }
+
+    /// Removes every entry, keeping the slots.
+    pub fn clear(&mut self) {
+        for slot in self.slots.iter_mut() {
+            *slot = None;
+        }
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            slots: self.slots.iter(),
+            remaining: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            slots: self.slots.iter_mut(),
+            remaining: self.len,
+        }
+    }
+
+    pub fn keys(&self) -> impl ExactSizeIterator<Item = &K> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl ExactSizeIterator<Item = &V> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl ExactSizeIterator<Item = &mut V> + '_ {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    /// Keeps only the entries for which `keep` returns `true`.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut keep: F) {
+        // Walk backwards, finishing on a slot that's empty or holds an entry in its home slot
+        // (there's always one, even in a full table). Removing an entry only shifts back the
+        // entries after it, which we've already been past, and the shifting stops at that slot,
+        // so every entry is looked at exactly once.
+        let Some(head) = (0..self.slots.len()).find(|&index| match &self.slots[index] {
+            None => true,
+            Some(bucket) => self.probe_distance(bucket.hash, index) == 0,
+        }) else {
+            return;
+        };
+
+        let mask = self.mask();
+        let mut index = head;
+        for _ in 0..self.slots.len() {
+            index = index.wrapping_sub(1) & mask;
+            if let Some(bucket) = &mut self.slots[index] {
+                if !keep(&bucket.key, &mut bucket.value) {
+                    self.remove_at(index);
+                }
+            }
+        }
+    }
+
+    /// How many slots are needed to hold `capacity` entries under the load factor.
+    fn slots_for(&self, capacity: usize) -> usize {
+        if capacity == 0 {
+            return 0;
+        }
+        // rounding up by hand, since `f64::ceil` needs `std`
+        let exact = capacity as f64 / self.max_load_factor;
+        let mut slots = exact as usize;
+        if (slots as f64) < exact {
+            slots += 1;
+        }
+        slots.max(8).next_power_of_two()
+    }
+
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    /// How far the entry in slot `index` is from the slot its hash points to.
+    fn probe_distance(&self, hash: u64, index: usize) -> usize {
+        index.wrapping_sub(hash as usize) & self.mask()
+    }
+
+    /// Makes sure there's room for `additional` more entries without going over the load factor.
+    fn reserve(&mut self, additional: usize) {
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        if required > self.capacity() {
+            // at least double, so inserting stays amortised O(1)
+            let slots = self.slots_for(required).max(self.slots.len() * 2);
+            self.resize(slots);
+        }
+    }
+
+    /// Moves every entry into a fresh table of `slot_count` slots.
+    fn resize(&mut self, slot_count: usize) {
+        let old_slots = core::mem::replace(&mut self.slots, Self::empty_slots(slot_count));
+        self.len = 0;
+        for bucket in old_slots.into_iter().flatten() {
+            self.place(bucket);
+        }
+    }
+
+    fn empty_slots(slot_count: usize) -> MyVec<Option<Bucket<K, V>>> {
+        let mut slots = MyVec::with_capacity(slot_count);
+        slots.extend((0..slot_count).map(|_| None));
+        slots
+    }
+
+    /// Puts a bucket whose key isn't in the map into the table, and returns the slot it ended up in.
+    /// There must be a free slot.
+    fn place(&mut self, bucket: Bucket<K, V>) -> usize {
+        let mask = self.mask();
+        let mut index = bucket.hash as usize & mask;
+        let mut distance = 0;
+        let mut carrying = bucket;
+        // where the new bucket went, once it's been put down
+        let mut placed_at = None;
+
+        loop {
+            match &mut self.slots[index] {
+                slot @ None => {
+                    *slot = Some(carrying);
+                    self.len += 1;
+                    return placed_at.unwrap_or(index);
+                }
+                Some(existing) => {
+                    let existing_distance = index.wrapping_sub(existing.hash as usize) & mask;
+                    if existing_distance < distance {
+                        // Robin Hood: the entry here is closer to home than we are, so it gives
+                        // up its slot and we carry it on instead
+                        core::mem::swap(existing, &mut carrying);
+                        placed_at.get_or_insert(index);
+                        distance = existing_distance;
+                    }
+                }
+            }
+            index = (index + 1) & mask;
+            distance += 1;
+        }
+    }
+
+    /// Takes the entry out of slot `index`, then shifts back the entries after it that aren't
+    /// already in their home slot, so there's never a gap in a probe sequence.
+    fn remove_at(&mut self, index: usize) -> (K, V) {
+        let bucket = self.slots[index].take().unwrap();
+        self.len -= 1;
+
+        let mask = self.mask();
+        let mut hole = index;
+        loop {
+            let next = (hole + 1) & mask;
+            match &self.slots[next] {
+                Some(following) if self.probe_distance(following.hash, next) > 0 => {
+                    self.slots[hole] = self.slots[next].take();
+                    hole = next;
+                }
+                _ => break,
+            }
+        }
+
+        (bucket.key, bucket.value)
+    }
+
+    fn bucket(&self, index: usize) -> &Bucket<K, V> {
+        self.slots[index].as_ref().unwrap()
+    }
+
+    fn bucket_mut(&mut self, index: usize) -> &mut Bucket<K, V> {
+        self.slots[index].as_mut().unwrap()
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> MyHashMap<K, V, S> {
+    /// Inserts `value` under `key`, returning the value that was there before, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hash_builder.hash_one(key), key)?;
+        Some(&self.bucket(index).value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hash_builder.hash_one(key), key)?;
+        Some(&mut self.bucket_mut(index).value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let bucket = self.bucket(self.find(self.hash_builder.hash_one(key), key)?);
+        Some((&bucket.key, &bucket.value))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(self.hash_builder.hash_one(key), key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(self.hash_builder.hash_one(key), key)?;
+        Some(self.remove_at(index))
+    }
+
+    /// Gets the entry for `key`, to look at or change in place.
+    ///
+    /// ```
+    /// use wk9_01_implementing_vec::MyHashMap;
+    ///
+    /// let mut counts = MyHashMap::new();
+    /// for word in "the cat and the hat".split(' ') {
+    ///     counts.entry(word).and_modify(|count| *count += 1).or_insert(1);
+    /// }
+    /// assert_eq!(counts["the"], 2);
+    /// assert_eq!(counts["hat"], 1);
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let hash = self.hash_builder.hash_one(&key);
+        match self.find(hash, &key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+            None => Entry::Vacant(VacantEntry {
+                map: self,
+                hash,
+                key,
+            }),
+        }
+    }
+
+    /// The slot holding `key`, if it's in the map.
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        if self.slots.is_empty() {
+            return None;
+        }
+
+        let mask = self.mask();
+        let mut index = hash as usize & mask;
+        let mut distance = 0;
+        loop {
+            let bucket = self.slots[index].as_ref()?;
+            // if `key` were here it would have taken this slot from the poorer entry when it was inserted
+            if self.probe_distance(bucket.hash, index) < distance {
+                return None;
+            }
+            if bucket.hash == hash && bucket.key.borrow() == key {
+                return Some(index);
+            }
+            index = (index + 1) & mask;
+            distance += 1;
+        }
+    }
+}
+
+/// A view into a single entry of a [`MyHashMap`], which may or may not be there yet.
+pub enum Entry<'a, K, V, S> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+pub struct OccupiedEntry<'a, K, V, S> {
+    map: &'a mut MyHashMap<K, V, S>,
+    index: usize,
+}
+
+pub struct VacantEntry<'a, K, V, S> {
+    map: &'a mut MyHashMap<K, V, S>,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S> {
+    /// Inserts `default` if the entry is vacant, and returns the value either way.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Runs `f` on the value if there is one.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.map.bucket(self.index).key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.map.bucket(self.index).value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.bucket_mut(self.index).value
+    }
+
+    /// Like `get_mut`, but the reference lives as long as the borrow of the map.
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.bucket_mut(self.index).value
+    }
+
+    /// Replaces the value, returning the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.map.remove_at(self.index)
+    }
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts `value` under the entry's key, and returns it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let map = self.map;
+        map.reserve(1);
+        let index = map.place(Bucket {
+            hash: self.hash,
+            key: self.key,
+            value,
+        });
+        &mut map.bucket_mut(index).value
+    }
+}
+
+impl<K, V> Default for MyHashMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for MyHashMap<K, V, S> {
+    fn clone(&self) -> Self {
+        // the hashes are saved, so a straight copy of the slots is still a valid table
+        let mut slots = MyVec::with_capacity(self.slots.len());
+        slots.extend(self.slots.iter().map(|slot| {
+            slot.as_ref().map(|bucket| Bucket {
+                hash: bucket.hash,
+                key: bucket.key.clone(),
+                value: bucket.value.clone(),
+            })
+        }));
+        Self {
+            slots,
+            len: self.len,
+            max_load_factor: self.max_load_factor,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for MyHashMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq, S: BuildHasher> PartialEq for MyHashMap<K, V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K: Hash + Eq, V: Eq, S: BuildHasher> Eq for MyHashMap<K, V, S> {}
+
+impl<K, Q, V, S> Index<&Q> for MyHashMap<K, V, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    /// # Panics
+    ///
+    /// Panics if `key` isn't in the map.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not in map")
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for MyHashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, V)> for MyHashMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+/// Iterator over a [`MyHashMap`]'s entries, in no particular order.
+pub struct Iter<'a, K, V> {
+    slots: crate::Iter<'a, Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bucket = self.slots.find_map(Option::as_ref)?;
+        self.remaining -= 1;
+        Some((&bucket.key, &bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            slots: self.slots.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+/// Iterator over a [`MyHashMap`]'s entries, with mutable values.
+pub struct IterMut<'a, K, V> {
+    slots: crate::IterMut<'a, Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bucket = self.slots.find_map(Option::as_mut)?;
+        self.remaining -= 1;
+        Some((&bucket.key, &mut bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+/// Owning iterator over a [`MyHashMap`]'s entries.
+pub struct IntoIter<K, V> {
+    slots: crate::IntoIter<Option<Bucket<K, V>>>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        let bucket = self.slots.find_map(|slot| slot)?;
+        self.remaining -= 1;
+        Some((bucket.key, bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+impl<K, V, S> IntoIterator for MyHashMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            slots: self.slots.into_iter(),
+            remaining: self.len,
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a MyHashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut MyHashMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    use super::*;
+    use crate::test_utils::{assert_panics, DropCounter};
+
+    /// Hashes everything to the same value, so every key fights over the same slots.
+    #[derive(Default)]
+    struct Collide;
+
+    impl Hasher for Collide {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    type Colliding = BuildHasherDefault<Collide>;
+
+    /// Hashes integers to themselves, so tests can pick which slot a key lives in.
+    #[derive(Default)]
+    struct Identity(u64);
+
+    impl Hasher for Identity {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes.iter().rev() {
+                self.0 = self.0 << 8 | u64::from(byte);
+            }
+        }
+    }
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut map = MyHashMap::new();
+        assert_eq!(map.get("hello"), None);
+        assert_eq!(map.insert("hello".to_string(), 1), None);
+        assert_eq!(map.insert("world".to_string(), 2), None);
+        assert_eq!(map.insert("hello".to_string(), 3), Some(1));
+        assert_eq!(map.len(), 2);
+
+        // `String` keys can be looked up with a `&str`
+        assert_eq!(map.get("hello"), Some(&3));
+        *map.get_mut("world").unwrap() += 10;
+        assert_eq!(map["world"], 12);
+        assert!(map.contains_key("world"));
+
+        assert_eq!(map.remove("hello"), Some(3));
+        assert_eq!(map.remove("hello"), None);
+        assert_eq!(map.len(), 1);
+        assert_panics(|| map["hello"]);
+    }
+
+    #[test]
+    fn test_entry() {
+        // the example from `wk2_00_collections`
+        let mut map: MyHashMap<String, i32> = [("hello".to_string(), 1), ("world".to_string(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        map.entry("hello".to_string())
+            .and_modify(|x| *x += 1)
+            .or_insert(1);
+        map.entry("new".to_string())
+            .and_modify(|x| *x += 1)
+            .or_insert(1);
+        assert_eq!(map["hello"], 2);
+        assert_eq!(map["new"], 1);
+
+        *map.entry("world".to_string()).or_default() *= 10;
+        assert_eq!(map["world"], 20);
+        assert_eq!(map.entry("nope".to_string()).key(), "nope");
+
+        match map.entry("world".to_string()) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), ("world".to_string(), 20)),
+            Entry::Vacant(_) => panic!("`world` should be there"),
+        }
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_collisions() {
+        // with every hash the same, this is all probing and backward shifting
+        let mut map = MyHashMap::with_hasher(Colliding::default());
+        for i in 0..50 {
+            map.insert(i, i * 10);
+        }
+        for i in (0..50).step_by(3) {
+            assert_eq!(map.remove(&i), Some(i * 10));
+        }
+        for i in 0..50 {
+            assert_eq!(map.get(&i), (i % 3 != 0).then_some(&(i * 10)), "key {i}");
+        }
+        map.retain(|&key, _| key % 2 == 0);
+        let mut keys: Vec<_> = map.keys().copied().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            (0..50)
+                .filter(|i| i % 3 != 0 && i % 2 == 0)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_retain_in_place() {
+        // a full table of 8 where the keys all want the last two slots, so the probe sequences
+        // wrap around past the end and removing has to shift entries back across it
+        let keys = [6u64, 7, 14, 15, 22, 23, 30, 31];
+        let mut map = MyHashMap::with_hasher(BuildHasherDefault::<Identity>::default())
+            .with_max_load_factor(1.0);
+        map.extend(keys.iter().map(|&key| (key, key * 10)));
+        assert_eq!(map.slots.len(), 8);
+        let slots = map.slots.as_ptr();
+
+        let mut seen = Vec::new();
+        map.retain(|&key, value| {
+            seen.push(key);
+            *value += 1;
+            key % 3 != 0
+        });
+        seen.sort();
+        assert_eq!(seen, keys, "every entry should be looked at exactly once");
+
+        assert_eq!(map.slots.as_ptr(), slots);
+        assert_eq!(map.len(), 5);
+        for key in keys {
+            let expected = key * 10 + 1;
+            assert_eq!(
+                map.get(&key),
+                (key % 3 != 0).then_some(&expected),
+                "key {key}"
+            );
+        }
+
+        map.retain(|_, _| false);
+        assert!(map.is_empty());
+        assert!(map.slots.iter().all(Option::is_none));
+        MyHashMap::<u8, u8>::new().retain(|_, _| unreachable!());
+    }
+
+    #[test]
+    fn test_load_factor() {
+        let mut map = MyHashMap::new().with_max_load_factor(0.5);
+        assert_eq!(map.capacity(), 0);
+        for i in 0..100 {
+            map.insert(i, ());
+            assert!(map.len() <= map.capacity());
+            assert!(map.len() as f64 <= map.slots.len() as f64 * 0.5);
+        }
+
+        let map = MyHashMap::<u8, u8>::with_capacity(100);
+        assert!(map.capacity() >= 100);
+        assert_eq!(map.slots.len(), 128);
+
+        assert_panics(|| MyHashMap::<u8, u8>::new().with_max_load_factor(0.0));
+        assert_panics(|| MyHashMap::<u8, u8>::new().with_max_load_factor(1.5));
+
+        // a full table still works
+        let mut full = MyHashMap::new().with_max_load_factor(1.0);
+        full.extend((0..8).map(|i| (i, i)));
+        assert_eq!(full.get(&8), None);
+        assert_eq!(full.slots.len(), 8);
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut map: MyHashMap<_, _> = (0..10).map(|i| (i, i)).collect();
+        for (_, value) in &mut map {
+            *value *= 2;
+        }
+        for value in map.values_mut() {
+            *value += 1;
+        }
+        let mut pairs: Vec<_> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        pairs.sort();
+        assert_eq!(pairs, (0..10).map(|i| (i, i * 2 + 1)).collect::<Vec<_>>());
+        assert_eq!(map.iter().len(), 10);
+        assert_eq!(map.values().sum::<i32>(), 100);
+
+        let mut owned: Vec<_> = map.clone().into_iter().collect();
+        owned.sort();
+        assert_eq!(owned, pairs);
+        assert_eq!(map.clone(), map);
+        assert_eq!(format!("{:?}", MyHashMap::from_iter([(1, 2)])), "{1: 2}");
+    }
+
+    #[test]
+    fn test_drops() {
+        let counter = DropCounter::new();
+        let mut map = MyHashMap::new();
+        for i in 0..20 {
+            map.insert(i, counter.track(i));
+        }
+        drop(map.insert(0, counter.track(100)));
+        assert_eq!(counter.drops(), 1);
+        drop(map.remove(&1));
+        assert_eq!(counter.drops(), 2);
+
+        let mut iter = map.into_iter();
+        drop(iter.next());
+        drop(iter);
+        assert_eq!(counter.drops(), 21);
+    }
+
+    #[test]
+    fn test_against_std() {
+        // a cheap xorshift, so the sequence of operations is the same every run
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut ours = MyHashMap::new();
+        let mut std = HashMap::new();
+        for _ in 0..5000 {
+            // a small key space, so there are plenty of hits, overwrites and removes
+            let key = next() % 300;
+            match next() % 4 {
+                0 | 1 => {
+                    let value = next();
+                    assert_eq!(ours.insert(key, value), std.insert(key, value));
+                }
+                2 => assert_eq!(ours.remove(&key), std.remove(&key)),
+                _ => assert_eq!(ours.get(&key), std.get(&key)),
+            }
+            assert_eq!(ours.len(), std.len());
+        }
+        assert!(std.iter().all(|(key, value)| ours.get(key) == Some(value)));
+    }
+}
@@ -0,0 +1,313 @@
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ptr::NonNull;
+
+use crate::{Global, GrowthPolicy, MyAllocator, MyVec, RawBuf};
+
+/// Borrowing iterator over a [`MyVec`] (or another of the crate's containers), created by [`MyVec::iter`].
+pub struct Iter<'a, T> {
+    ptr: NonNull<T>,
+    start: usize,
+    end: usize,
+    // we hand out `&'a T`s, so we act like we're holding one
+    _marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    // takes a slice rather than a `MyVec` so the other containers in the crate can share it
+    pub(crate) fn new(slice: &'a [T]) -> Self {
+        Self {
+            ptr: NonNull::from(slice).cast(),
+            start: 0,
+            end: slice.len(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.start == self.end {
+            None
+        } else {
+            // SAFETY: `start < end <= size`, so the element is initialised,
+            //         and the vec is borrowed for 'a so it can't be mutated underneath us
+            let value = unsafe { self.ptr.add(self.start).as_ref() };
+            self.start += 1;
+            Some(value)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.start == self.end {
+            None
+        } else {
+            self.end -= 1;
+            // SAFETY: same as `next`, `end` is still at least `start`
+            Some(unsafe { self.ptr.add(self.end).as_ref() })
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+// SAFETY: an `Iter` is just a `&[T]` in disguise
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self { ..*self }
+    }
+}
+
+/// Mutably borrowing iterator over a [`MyVec`] (or another of the crate's containers), created by [`MyVec::iter_mut`].
+pub struct IterMut<'a, T> {
+    ptr: NonNull<T>,
+    start: usize,
+    end: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> IterMut<'a, T> {
+    pub(crate) fn new(slice: &'a mut [T]) -> Self {
+        Self {
+            end: slice.len(),
+            ptr: NonNull::from(slice).cast(),
+            start: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.start == self.end {
+            None
+        } else {
+            // SAFETY: `start < end <= size`, and every index is handed out at most once,
+            //         so no two `&mut` we return ever alias
+            let value = unsafe { self.ptr.add(self.start).as_mut() };
+            self.start += 1;
+            Some(value)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        if self.start == self.end {
+            None
+        } else {
+            self.end -= 1;
+            // SAFETY: same as `next`
+            Some(unsafe { self.ptr.add(self.end).as_mut() })
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+// SAFETY: an `IterMut` is just a `&mut [T]` in disguise
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+/// Owning iterator over a [`MyVec`], created by `MyVec::into_iter`.
+///
+/// Any elements that haven't been yielded yet are dropped along with the iterator.
+pub struct IntoIter<T, A: MyAllocator = Global> {
+    // frees itself when the iterator is dropped
+    buf: RawBuf<T, A>,
+    start: usize,
+    end: usize,
+    // we own `T`s, so drop check needs to know about them
+    _marker: PhantomData<T>,
+}
+
+impl<T, A: MyAllocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            // SAFETY: `start < end`, and everything in `start..end` is initialised and not yet moved out
+            let value = unsafe { self.buf.ptr().add(self.start).read() };
+            self.start += 1;
+            Some(value)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T, A: MyAllocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            self.end -= 1;
+            // SAFETY: same as `next`
+            Some(unsafe { self.buf.ptr().add(self.end).read() })
+        }
+    }
+}
+
+impl<T, A: MyAllocator> ExactSizeIterator for IntoIter<T, A> {}
+impl<T, A: MyAllocator> FusedIterator for IntoIter<T, A> {}
+
+impl<T, A: MyAllocator> Drop for IntoIter<T, A> {
+    fn drop(&mut self) {
+        // drop whatever the caller didn't take, all in one go so a panicking destructor
+        // doesn't leak the rest (then `buf` frees the memory)
+        // SAFETY: `start..end` is exactly the elements that are still initialised
+        unsafe {
+            let remaining = core::ptr::slice_from_raw_parts_mut(
+                self.buf.ptr().add(self.start),
+                self.end - self.start,
+            );
+            remaining.drop_in_place();
+        }
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> IntoIterator for MyVec<T, A, G> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> IntoIter<T, A> {
+        // the iterator takes over the buffer, so `MyVec`'s destructor mustn't run
+        let vec = ManuallyDrop::new(self);
+        IntoIter {
+            // SAFETY: `vec` is never used or dropped again, so the buffer is only owned once
+            buf: unsafe { core::ptr::read(&vec.buf) },
+            start: 0,
+            end: vec.size,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, A: MyAllocator, G: GrowthPolicy> IntoIterator for &'a MyVec<T, A, G> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: MyAllocator, G: GrowthPolicy> IntoIterator for &'a mut MyVec<T, A, G> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_panics, from_slice, tracked, DropCounter};
+
+    #[test]
+    fn test_iter() {
+        let vec = from_slice(&[1, 2, 3, 4]);
+
+        let mut seen = Vec::new();
+        for x in &vec {
+            seen.push(*x);
+        }
+        assert_eq!(seen, [1, 2, 3, 4]);
+
+        let mut iter = vec.iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(MyVec::<i32>::new().iter().next(), None);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut vec = from_slice(&[1, 2, 3]);
+        for x in &mut vec {
+            *x *= 10;
+        }
+        if let Some(last) = vec.iter_mut().next_back() {
+            *last += 1;
+        }
+        assert_eq!(vec.iter().copied().collect::<Vec<_>>(), [10, 20, 31]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let vec = from_slice(&[1, 2, 3, 4, 5]);
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next_back(), Some(5));
+        assert_eq!(iter.collect::<Vec<_>>(), [2, 3, 4]);
+
+        let vec = from_slice(&[1, 2, 3]);
+        assert_eq!(vec.into_iter().rev().collect::<Vec<_>>(), [3, 2, 1]);
+    }
+
+    #[test]
+    fn test_into_iter_drops_remaining() {
+        let counter = DropCounter::new();
+        let vec = tracked(&counter, &[0, 1, 2, 3, 4, 5]);
+
+        let mut iter = vec.into_iter();
+        drop(iter.next());
+        drop(iter.next_back());
+        assert_eq!(counter.drops(), 2);
+
+        drop(iter);
+        assert_eq!(counter.drops(), 6);
+    }
+
+    #[test]
+    fn test_into_iter_panicking_drop() {
+        let counter = DropCounter::new();
+        let mut vec = MyVec::new();
+        vec.push(counter.track(0));
+        vec.push(counter.track(1));
+        vec.push(counter.track_panicking(2));
+        vec.push(counter.track(3));
+        vec.push(counter.track(4));
+
+        let mut iter = vec.into_iter();
+        drop(iter.next());
+        assert_panics(move || drop(iter));
+        // the ones after the panicking element still get dropped
+        assert_eq!(counter.drops(), 5);
+    }
+}
@@ -1,28 +1,249 @@
-use std::alloc::Layout;
+// without the `std` feature this only needs `core` and `alloc`, but the tests always get `std`
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+#![cfg_attr(feature = "nightly", feature(dropck_eyepatch))]
 
-pub struct MyVec<T> {
-    ptr: *mut T, // you can actually convert between *const T and *mut T with the `as` keyword freely... they're just there to signal intent
+extern crate alloc;
+
+use core::marker::PhantomData;
+use core::ops::{Bound, Deref, DerefMut, Index, IndexMut, Range, RangeBounds};
+use core::slice::SliceIndex;
+
+mod allocator;
+#[cfg(target_has_atomic = "ptr")]
+pub mod append_vec;
+pub mod array_vec;
+pub mod binary_heap;
+pub mod bit_vec;
+mod drain;
+mod error;
+mod growth;
+pub mod hash_map;
+mod iter;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod mmap_vec;
+pub mod persistent_vec;
+mod raw_buf;
+pub mod slab;
+pub mod small_vec;
+mod sort;
+mod string;
+#[cfg(test)]
+mod test_utils;
+mod traits;
+pub mod vec_deque;
+
+pub use allocator::{BumpAllocator, CountingAllocator, Global, MyAllocator};
+#[cfg(target_has_atomic = "ptr")]
+pub use append_vec::ConcurrentAppendVec;
+pub use array_vec::MyArrayVec;
+pub use binary_heap::MyBinaryHeap;
+pub use bit_vec::MyBitVec;
+pub use drain::{Drain, Splice};
+pub use error::{FromUtf8Error, TryReserveError, TryReserveErrorKind};
+pub use growth::{Doubling, FixedIncrement, GrowthPolicy, OneAndAHalf, PageAligned};
+pub use hash_map::MyHashMap;
+pub use iter::{IntoIter, Iter, IterMut};
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub use mmap_vec::{MmapVec, Pod};
+pub use persistent_vec::PersistentVec;
+use raw_buf::RawBuf;
+pub use slab::MySlab;
+pub use small_vec::MySmallVec;
+pub use string::MyString;
+pub use vec_deque::MyVecDeque;
+
+/// A growable array, like `Vec<T>`, that gets its memory from `A` and grows as `G` says
+/// (doubling by default, see [`GrowthPolicy`]).
+///
+/// `MyVec<T>` is `Send`/`Sync` exactly when `T` is (and the allocator is):
+///
+/// ```compile_fail
+/// use std::rc::Rc;
+/// use wk9_01_implementing_vec::MyVec;
+///
+/// let mut vec = MyVec::new();
+/// vec.push(Rc::new(1));
+/// std::thread::spawn(move || vec.len()); // `Rc` isn't `Send`
+/// ```
+///
+/// ```compile_fail
+/// use std::cell::Cell;
+/// use wk9_01_implementing_vec::MyVec;
+///
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<MyVec<Cell<i32>>>(); // `Cell` isn't `Sync`
+/// ```
+///
+/// It's covariant in `T`, but (like `Vec`) `&mut MyVec<T>` isn't, since that would let you sneak
+/// a short-lived reference into a vector that's meant to hold long-lived ones:
+///
+/// ```compile_fail
+/// use wk9_01_implementing_vec::MyVec;
+///
+/// fn shorten<'r, 'a>(vec: &'r mut MyVec<&'static str>) -> &'r mut MyVec<&'a str> {
+///     vec
+/// }
+/// ```
+pub struct MyVec<T, A: MyAllocator = Global, G: GrowthPolicy = Doubling> {
+    // `RawBuf` holds a `NonNull<T>`, which is covariant in `T` like `*const T` is, so a `MyVec<&'static str>`
+    // can be used as a `MyVec<&'a str>`. A `*mut T` would make `MyVec` invariant, which is stricter than it
+    // needs to be since we never hand out a `&mut` to the buffer that outlives a `&mut self`
+    buf: RawBuf<T, A>,
     size: usize,
-    capacity: usize,
+    // tells drop check that dropping a `MyVec<T>` may drop some `T`s
+    _marker: PhantomData<T>,
+    // the policy is just a type, we never hold one. `fn() -> G` keeps it out of the auto traits
+    _growth: PhantomData<fn() -> G>,
 }
 
 impl<T> MyVec<T> {
-    const INITIAL_CAPACITY: usize = 8;
-
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    /// Creates an empty vector with room for at least `capacity` elements before it reallocates.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+
+    /// Like [`MyVec::with_capacity`], but returns an error instead of panicking or aborting.
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_in(capacity, Global)
+    }
+}
+
+// The constructors stick to the default growth policy, since a defaulted type parameter doesn't
+// help type inference: `MyVec::new_in(alloc)` would otherwise need the policy spelling out.
+impl<T, A: MyAllocator> MyVec<T, A> {
+    /// Creates an empty vector that will get its memory from `alloc`.
+    pub fn new_in(alloc: A) -> Self {
+        Self::from_buf(RawBuf::new_in(alloc))
+    }
+
+    /// Like [`MyVec::with_capacity`], but gets its memory from `alloc`.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Self::from_buf(RawBuf::with_capacity_in(capacity, alloc))
+    }
+
+    /// Like [`MyVec::try_with_capacity`], but gets its memory from `alloc`.
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        RawBuf::try_with_capacity_in(capacity, alloc).map(Self::from_buf)
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> MyVec<T, A, G> {
+    /// Creates an empty vector that gets its memory from `alloc` and grows according to `G`.
+    ///
+    /// ```
+    /// use wk9_01_implementing_vec::{FixedIncrement, Global, MyVec};
+    ///
+    /// let mut vec = MyVec::<u8, Global, FixedIncrement<100>>::with_growth_in(Global);
+    /// vec.push(1);
+    /// assert_eq!(vec.capacity(), 100);
+    /// ```
+    pub fn with_growth_in(alloc: A) -> Self {
+        Self::from_buf(RawBuf::new_in(alloc))
+    }
+
+    /// An empty vector using `buf` as its buffer.
+    fn from_buf(buf: RawBuf<T, A>) -> Self {
         Self {
-            ptr: std::ptr::null_mut(),
+            buf,
             size: 0,
-            capacity: 0,
+            _marker: PhantomData,
+            _growth: PhantomData,
+        }
+    }
+
+    /// The allocator backing this vector.
+    pub fn allocator(&self) -> &A {
+        self.buf.allocator()
+    }
+
+    /// The number of elements in the vector.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// The number of elements the vector can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Reserves room for at least `additional` more elements, possibly more to avoid frequent reallocations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize` or would need more than `isize::MAX` bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.buf.reserve::<G>(self.size, additional);
+    }
+
+    /// Reserves room for exactly `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Same as [`MyVec::reserve`].
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.buf.reserve_exact(self.size, additional);
+    }
+
+    /// Like [`MyVec::reserve`], but returns an error instead of panicking or aborting.
+    /// The vector is left unchanged if this fails.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.buf.try_reserve::<G>(self.size, additional)
+    }
+
+    /// Like [`MyVec::reserve_exact`], but returns an error instead of panicking or aborting.
+    /// The vector is left unchanged if this fails.
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.buf.try_reserve_exact(self.size, additional)
+    }
+
+    /// Shrinks the capacity as close to the length as possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Shrinks the capacity down to `min_capacity`, but never below the length.
+    /// Does nothing if the capacity is already smaller.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.buf.shrink_to(self.size.max(min_capacity));
+    }
+
+    /// Drops every element from index `len` onwards. Does nothing if `len` is past the end.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.size {
+            return;
         }
+
+        let tail_len = self.size - len;
+
+        // shrink first, so if a destructor panics we don't try to drop the tail again
+        self.size = len;
+
+        // SAFETY: `len..len + tail_len` was initialised and is now outside the vector
+        unsafe {
+            let tail = core::ptr::slice_from_raw_parts_mut(self.ptr_to_elem(len), tail_len);
+            tail.drop_in_place();
+        }
+    }
+
+    /// Drops every element, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.truncate(0);
     }
 
     pub fn push(&mut self, value: T) {
-        if self.size == self.capacity {
-            self.expand_capacity();
+        if self.size == self.capacity() {
+            self.buf.grow_one::<G>(self.size);
         }
 
-        // SAFETY: We just expanded the capacity if necessary so self.size is less than self.capacity
+        // SAFETY: We just expanded the capacity if necessary so self.size is less than the capacity
         let ptr = unsafe { self.ptr_to_elem(self.size) };
 
         self.size += 1;
@@ -34,6 +255,23 @@ impl<T> MyVec<T> {
         unsafe { ptr.write(value) };
     }
 
+    /// Like [`MyVec::push`], but returns an error instead of panicking or aborting if the vector
+    /// needs to grow and can't. `value` comes back with the error, so it can be retried or put
+    /// somewhere else.
+    pub fn try_push(&mut self, value: T) -> Result<(), (T, TryReserveError)> {
+        if self.size == self.capacity() {
+            if let Err(err) = self.buf.try_grow_one::<G>(self.size) {
+                return Err((value, err));
+            }
+        }
+
+        // SAFETY: same as `push`
+        unsafe { self.ptr_to_elem(self.size).write(value) };
+        self.size += 1;
+
+        Ok(())
+    }
+
     pub fn pop(&mut self) -> Option<T> {
         if self.size == 0 {
             None
@@ -64,68 +302,578 @@ impl<T> MyVec<T> {
         }
     }
 
-    fn expand_capacity(&mut self) {
-        if self.capacity == 0 {
-            let layout = Layout::array::<T>(Self::INITIAL_CAPACITY).unwrap();
-            // SAFETY: Layout has non-zero size
-            let ptr = unsafe { std::alloc::alloc(layout) };
+    /// Inserts `value` at `index`, shifting everything after it one to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, value: T) {
+        let len = self.size;
+        if index > len {
+            panic!("insertion index (is {index}) should be <= len (is {len})");
+        }
 
-            self.ptr = ptr as _;
-            self.capacity = Self::INITIAL_CAPACITY;
-        } else {
-            let new_capacity = self.capacity * 2;
-            // SAFETY: We allocated `self.ptr` ourself with a layout equal to `old_layout`,
-            //         and the new layout is greater than zero bytes in size,
-            //         and we assume it doesn't overflow
-            let new_ptr = unsafe {
-                std::alloc::realloc(
-                    self.ptr as *mut u8,
-                    Self::layout_for(self.capacity),
-                    new_capacity,
-                )
-            };
+        if len == self.capacity() {
+            self.buf.grow_one::<G>(len);
+        }
 
-            self.ptr = new_ptr as _;
-            self.capacity = new_capacity;
+        // SAFETY: `index <= len < capacity`, so both the shifted range and the new slot are in the buffer.
+        //         `copy` (unlike `copy_nonoverlapping`) is fine with the ranges overlapping
+        unsafe {
+            let ptr = self.ptr_to_elem(index);
+            core::ptr::copy(ptr, ptr.add(1), len - index);
+            ptr.write(value);
         }
+        self.size += 1;
     }
 
-    fn layout_for(n_elems: usize) -> Layout {
-        Layout::array::<T>(n_elems).unwrap()
+    /// Removes and returns the element at `index`, shifting everything after it one to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.size;
+        if index >= len {
+            panic!("removal index (is {index}) should be < len (is {len})");
+        }
+
+        // SAFETY: `index < len`, so the element is initialised. Once it's read out we own it,
+        //         and the slot is immediately overwritten by the shift
+        unsafe {
+            let ptr = self.ptr_to_elem(index);
+            let value = ptr.read();
+            core::ptr::copy(ptr.add(1), ptr, len - index - 1);
+            self.size -= 1;
+            value
+        }
     }
 
-    /// # Safety
+    /// Removes and returns the element at `index`, replacing it with the last element.
+    /// This doesn't preserve ordering, but is O(1).
     ///
-    /// The caller must ensure that `index` is less than `self.capacity`.
-    unsafe fn ptr_to_elem(&self, index: usize) -> *mut T {
-        unsafe { self.ptr.add(index) }
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let len = self.size;
+        if index >= len {
+            panic!("swap_remove index (is {index}) should be < len (is {len})");
+        }
+
+        // SAFETY: both `index` and `len - 1` are initialised, and the last slot is
+        //         treated as uninitialised once we shrink `size`
+        unsafe {
+            let value = self.ptr_to_elem(index).read();
+            core::ptr::copy(self.ptr_to_elem(len - 1), self.ptr_to_elem(index), 1);
+            self.size -= 1;
+            value
+        }
     }
-}
 
-impl<T> Drop for MyVec<T> {
-    fn drop(&mut self) {
-        if self.capacity != 0 {
-            for index in 0..self.size {
+    /// Keeps only the elements for which `keep` returns `true`, preserving their order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        self.retain_mut(|value| keep(value));
+    }
+
+    /// Like [`MyVec::retain`], but `keep` gets to mutate each element.
+    pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut keep: F) {
+        // Elements are kept by shuffling them left over the gaps left by removed ones.
+        // If `keep` or a destructor panics partway through, the guard still closes the gap
+        // and fixes up `size`, so nothing gets dropped twice or left as a hole.
+        struct BackshiftOnDrop<'a, T, A: MyAllocator, G: GrowthPolicy> {
+            vec: &'a mut MyVec<T, A, G>,
+            processed: usize,
+            deleted: usize,
+            original_len: usize,
+        }
+
+        impl<T, A: MyAllocator, G: GrowthPolicy> Drop for BackshiftOnDrop<'_, T, A, G> {
+            fn drop(&mut self) {
+                if self.deleted > 0 {
+                    // SAFETY: everything from `processed` onwards is still initialised and untouched,
+                    //         and `processed - deleted` is where the kept prefix ends
+                    unsafe {
+                        core::ptr::copy(
+                            self.vec.ptr_to_elem(self.processed),
+                            self.vec.ptr_to_elem(self.processed - self.deleted),
+                            self.original_len - self.processed,
+                        );
+                    }
+                }
+                self.vec.size = self.original_len - self.deleted;
+            }
+        }
+
+        let original_len = self.size;
+        // if we somehow leak the guard, the worst case is leaking elements rather than double dropping
+        self.size = 0;
+
+        let mut guard = BackshiftOnDrop {
+            vec: self,
+            processed: 0,
+            deleted: 0,
+            original_len,
+        };
+
+        while guard.processed != original_len {
+            // SAFETY: `processed < original_len`, and this element hasn't been moved or dropped yet
+            let current = unsafe { guard.vec.ptr_to_elem(guard.processed) };
+
+            if !keep(unsafe { &mut *current }) {
+                // count it as gone *before* dropping, so a panicking destructor doesn't get a second go
+                guard.processed += 1;
+                guard.deleted += 1;
+                // SAFETY: we own this element, and it's now outside the range the guard will shift
+                unsafe { current.drop_in_place() };
+                continue;
+            }
+
+            if guard.deleted > 0 {
+                // SAFETY: the destination slot was dropped (or already moved out of) earlier
+                unsafe { core::ptr::copy_nonoverlapping(current, current.sub(guard.deleted), 1) };
+            }
+            guard.processed += 1;
+        }
+    }
+
+    /// Removes consecutive repeated elements, keeping the first of each run.
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
+    }
+
+    /// Removes consecutive elements that map to the same key.
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes consecutive elements for which `same_bucket(current, previous)` returns `true`.
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same_bucket: F) {
+        // `read` is the next element to look at, and everything before `write` is kept.
+        // The guard closes the gap between them even if `same_bucket` or a destructor panics.
+        struct FillGapOnDrop<'a, T, A: MyAllocator, G: GrowthPolicy> {
+            vec: &'a mut MyVec<T, A, G>,
+            read: usize,
+            write: usize,
+            original_len: usize,
+        }
+
+        impl<T, A: MyAllocator, G: GrowthPolicy> Drop for FillGapOnDrop<'_, T, A, G> {
+            fn drop(&mut self) {
+                // SAFETY: `read..original_len` is initialised and untouched, and `write..read` is a hole
                 unsafe {
-                    // SAFETY: We just checked that `index` is less than `self.size`
-                    let ptr = self.ptr_to_elem(index);
+                    core::ptr::copy(
+                        self.vec.ptr_to_elem(self.read),
+                        self.vec.ptr_to_elem(self.write),
+                        self.original_len - self.read,
+                    );
+                }
+                self.vec.size = self.original_len - (self.read - self.write);
+            }
+        }
+
+        let original_len = self.size;
+        if original_len <= 1 {
+            return;
+        }
 
-                    // SAFETY: We just checked that `index` is less than `self.size`
-                    ptr.drop_in_place();
+        self.size = 0;
+        let mut guard = FillGapOnDrop {
+            vec: self,
+            read: 1,
+            write: 1,
+            original_len,
+        };
+
+        while guard.read < original_len {
+            // SAFETY: `read` is initialised, and `write - 1 < read` is the last element we kept
+            unsafe {
+                let current = guard.vec.ptr_to_elem(guard.read);
+                let previous = guard.vec.ptr_to_elem(guard.write - 1);
+
+                if same_bucket(&mut *current, &mut *previous) {
+                    guard.read += 1;
+                    current.drop_in_place();
+                } else {
+                    core::ptr::copy(current, guard.vec.ptr_to_elem(guard.write), 1);
+                    guard.write += 1;
+                    guard.read += 1;
                 }
             }
+        }
+    }
+
+    /// Removes the elements in `range`, returning them as an iterator.
+    ///
+    /// The removed elements are dropped along with the iterator if they aren't all consumed,
+    /// and the elements after `range` are shifted down when the iterator is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or its start is after its end.
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, A, G> {
+        let Range { start, end } = slice_range(range, self.size);
+        Drain::new(self, start, end)
+    }
+
+    /// Replaces the elements in `range` with the ones from `replace_with`, returning the removed
+    /// elements as an iterator. The replacement happens when the iterator is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Same as [`MyVec::drain`].
+    pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Splice<'_, I::IntoIter, A, G>
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        Splice::new(self.drain(range), replace_with.into_iter())
+    }
+
+    /// Splits the vector in two, returning everything from `at` onwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Self
+    where
+        A: Clone,
+    {
+        let len = self.size;
+        if at > len {
+            panic!("`at` split index (is {at}) should be <= len (is {len})");
+        }
+
+        let other_len = len - at;
+        let mut other = Self::from_buf(RawBuf::with_capacity_in(
+            other_len,
+            self.allocator().clone(),
+        ));
+
+        // SAFETY: `at..len` is initialised, `other` has room for it, and we shrink `self`
+        //         so the moved elements only belong to `other`
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.ptr_to_elem(at), other.ptr_to_elem(0), other_len);
+            self.size = at;
+            other.size = other_len;
+        }
+
+        other
+    }
+
+    /// Moves every element of `other` onto the end of `self`, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        let count = other.size;
+        self.reserve(count);
+
+        // SAFETY: we just reserved room for `count` more, and `other` forgets about the
+        //         elements so they're only owned once
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                other.ptr_to_elem(0),
+                self.ptr_to_elem(self.size),
+                count,
+            );
+            other.size = 0;
+        }
+        self.size += count;
+    }
+
+    /// Clones every element of `values` onto the end of the vector.
+    pub fn extend_from_slice(&mut self, values: &[T])
+    where
+        T: Clone,
+    {
+        self.reserve(values.len());
+
+        for value in values {
+            // bump `size` one at a time, so if a `clone` panics everything pushed so far is kept
+            // SAFETY: we reserved room for all of `values` above
+            unsafe { self.ptr_to_elem(self.size).write(value.clone()) };
+            self.size += 1;
+        }
+    }
+
+    /// Extracts a slice containing the entire vector.
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: `self.ptr` is non-null and aligned even before we allocate,
+        //         and the first `self.size` elements are initialised
+        unsafe { core::slice::from_raw_parts(self.buf.ptr(), self.size) }
+    }
+
+    /// Extracts a mutable slice containing the entire vector.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: same as `as_slice`, and we have unique access through `&mut self`
+        unsafe { core::slice::from_raw_parts_mut(self.buf.ptr(), self.size) }
+    }
+
+    /// Returns an iterator over shared references to the elements, front to back.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self.as_slice())
+    }
+
+    /// Returns an iterator over mutable references to the elements, front to back.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut::new(self.as_mut_slice())
+    }
+
+    /// Sorts the vector, keeping equal elements in the order they were in.
+    ///
+    /// This is our own merge sort rather than the slice one: O(n log n), and it allocates a scratch
+    /// buffer half the length of the vector. Already sorted runs are nearly free.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        sort::merge_sort(self, &mut T::lt);
+    }
+
+    /// Like [`MyVec::sort`], ordering by `compare`.
+    pub fn sort_by<F: FnMut(&T, &T) -> core::cmp::Ordering>(&mut self, mut compare: F) {
+        sort::merge_sort(self, &mut |a, b| compare(a, b).is_lt());
+    }
+
+    /// Like [`MyVec::sort`], ordering by the key `f` gives each element.
+    ///
+    /// `f` is called on every comparison, so if it's expensive use
+    /// [`MyVec::sort_by_cached_key`].
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        sort::merge_sort(self, &mut |a, b| f(a).lt(&f(b)));
+    }
+
+    /// Like [`MyVec::sort_by_key`], but only calls `f` once per element, storing the keys
+    /// in a separate vector while it sorts.
+    pub fn sort_by_cached_key<K: Ord, F: FnMut(&T) -> K>(&mut self, f: F) {
+        // tagging each key with where it came from makes them all different, so an unstable sort
+        // gives the same answer as a stable one
+        let mut keys: MyVec<(K, usize)> = self.iter().map(f).zip(0..).collect();
+        sort::quicksort(&mut keys, &mut |a, b| a.lt(b));
+
+        // `keys[i].1` says which element belongs at `i`. Elements before `i` have already been
+        // swapped away, so follow the chain of swaps to find where that element is now
+        for i in 0..self.len() {
+            let mut index = keys[i].1;
+            while index < i {
+                index = keys[index].1;
+            }
+            keys[i].1 = index;
+            self.swap(i, index);
+        }
+    }
+
+    /// Sorts the vector, without keeping equal elements in order.
+    ///
+    /// An introsort (quicksort that switches to heap sort if it's going badly), so it doesn't
+    /// allocate and is O(n log n) even on inputs picked to be bad for it.
+    pub fn sort_unstable(&mut self)
+    where
+        T: Ord,
+    {
+        sort::quicksort(self, &mut T::lt);
+    }
+
+    /// Like [`MyVec::sort_unstable`], ordering by `compare`.
+    pub fn sort_unstable_by<F: FnMut(&T, &T) -> core::cmp::Ordering>(&mut self, mut compare: F) {
+        sort::quicksort(self, &mut |a, b| compare(a, b).is_lt());
+    }
+
+    /// Like [`MyVec::sort_unstable`], ordering by the key `f` gives each element.
+    pub fn sort_unstable_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        sort::quicksort(self, &mut |a, b| f(a).lt(&f(b)));
+    }
+
+    /// Reorders the vector so the element at `index` is the one that would be there if it
+    /// were sorted, with nothing greater before it and nothing less after it. Returns the
+    /// elements before it, it, and the elements after it. O(n) on average.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn select_nth_unstable(&mut self, index: usize) -> (&mut [T], &mut T, &mut [T])
+    where
+        T: Ord,
+    {
+        self.select_nth_unstable_by(index, T::cmp)
+    }
+
+    /// Like [`MyVec::select_nth_unstable`], ordering by `compare`.
+    pub fn select_nth_unstable_by<F: FnMut(&T, &T) -> core::cmp::Ordering>(
+        &mut self,
+        index: usize,
+        mut compare: F,
+    ) -> (&mut [T], &mut T, &mut [T]) {
+        let len = self.len();
+        assert!(
+            index < len,
+            "select index (is {index}) should be < len (is {len})"
+        );
+
+        sort::select_nth(self, index, &mut |a, b| compare(a, b).is_lt());
+        let (before, rest) = self.split_at_mut(index);
+        let (nth, after) = rest.split_first_mut().unwrap();
+        (before, nth, after)
+    }
+
+    /// Binary searches a sorted vector for `value`. Returns `Ok` with the index of a match
+    /// (any of them, if there are several), or `Err` with where it could be inserted to keep the
+    /// vector sorted.
+    pub fn binary_search(&self, value: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        self.binary_search_by(|probe| probe.cmp(value))
+    }
+
+    /// Like [`MyVec::binary_search`], where `compare` says how each element compares to the
+    /// one being looked for.
+    pub fn binary_search_by<F: FnMut(&T) -> core::cmp::Ordering>(
+        &self,
+        compare: F,
+    ) -> Result<usize, usize> {
+        sort::binary_search_by(self, compare)
+    }
+
+    /// Like [`MyVec::binary_search`], for a vector sorted by the key `f` gives each element.
+    pub fn binary_search_by_key<K: Ord, F: FnMut(&T) -> K>(
+        &self,
+        key: &K,
+        mut f: F,
+    ) -> Result<usize, usize> {
+        self.binary_search_by(|probe| f(probe).cmp(key))
+    }
+
+    /// The index of the first element `pred` returns `false` for, assuming the vector is
+    /// partitioned: `true` for some prefix of it and `false` for the rest.
+    pub fn partition_point<P: FnMut(&T) -> bool>(&self, pred: P) -> usize {
+        sort::partition_point(self, pred)
+    }
 
-            // SAFETY: We allocated `self.ptr` ourself with a layout equal to `old_layout`
+    /// # Safety
+    ///
+    /// The caller must ensure that `index` is less than the capacity.
+    unsafe fn ptr_to_elem(&self, index: usize) -> *mut T {
+        unsafe { self.buf.ptr().add(index) }
+    }
+}
+
+#[cold]
+fn capacity_overflow() -> ! {
+    panic!("capacity overflow");
+}
+
+/// Turns any kind of range into `start..end`, checking it against `len` like slice indexing does.
+fn slice_range<R: RangeBounds<usize>>(range: R, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start
+            .checked_add(1)
+            .unwrap_or_else(|| panic!("attempted to index slice from after maximum usize")),
+        Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end
+            .checked_add(1)
+            .unwrap_or_else(|| panic!("attempted to index slice up to maximum usize")),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+
+    if start > end {
+        panic!("slice index starts at {start} but ends at {end}");
+    }
+    if end > len {
+        panic!("range end index {end} out of range for slice of length {len}");
+    }
+
+    start..end
+}
+
+/// Turns a failed allocation into a panic (overflow) or an abort (allocator failure), like `Vec` does.
+fn handle_reserve(result: Result<(), TryReserveError>) {
+    match result.map_err(|err| err.kind()) {
+        Ok(()) => {}
+        Err(TryReserveErrorKind::CapacityOverflow) => capacity_overflow(),
+        Err(TryReserveErrorKind::AllocError { layout }) => alloc::alloc::handle_alloc_error(layout),
+    }
+}
+
+impl<T> Default for MyVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> Deref for MyVec<T, A, G> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> DerefMut for MyVec<T, A, G> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+// same trick as `Vec`: anything that can index a slice (`usize` and every range type)
+// can index a `MyVec`, and out of bounds panics report both the index and the length
+impl<T, A: MyAllocator, G: GrowthPolicy, I: SliceIndex<[T]>> Index<I> for MyVec<T, A, G> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &I::Output {
+        Index::index(self.as_slice(), index)
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy, I: SliceIndex<[T]>> IndexMut<I> for MyVec<T, A, G> {
+    fn index_mut(&mut self, index: I) -> &mut I::Output {
+        IndexMut::index_mut(self.as_mut_slice(), index)
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> MyVec<T, A, G> {
+    /// Drops every element. Only `Drop` calls this, and `buf` frees the memory afterwards.
+    fn drop_elements(&mut self) {
+        // zero-sized elements still need their destructors run, even though there's no allocation
+        for index in 0..self.size {
             unsafe {
-                std::alloc::dealloc(self.ptr as _, Self::layout_for(self.capacity));
+                // SAFETY: We just checked that `index` is less than `self.size`
+                let ptr = self.ptr_to_elem(index);
+
+                // SAFETY: We just checked that `index` is less than `self.size`
+                ptr.drop_in_place();
             }
         }
     }
 }
 
+#[cfg(not(feature = "nightly"))]
+impl<T, A: MyAllocator, G: GrowthPolicy> Drop for MyVec<T, A, G> {
+    fn drop(&mut self) {
+        self.drop_elements();
+    }
+}
+
+// With `#[may_dangle]` we promise the compiler that dropping a `MyVec<T>` doesn't look at any `T`
+// except to drop it. That lets the `T`s hold references that are already dangling by the time the
+// vector is dropped, like `Vec` allows. `PhantomData<T>` still makes drop check consider `T`'s own destructor.
+#[cfg(feature = "nightly")]
+unsafe impl<#[may_dangle] T, A: MyAllocator, G: GrowthPolicy> Drop for MyVec<T, A, G> {
+    fn drop(&mut self) {
+        self.drop_elements();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::{from_slice, tracked, values};
 
     #[test]
     fn test_new() {
@@ -148,4 +896,391 @@ mod tests {
             println!("{:?}", vec.get(i));
         }
     }
+
+    #[test]
+    fn test_empty_slice() {
+        let mut vec = MyVec::<String>::new();
+        assert!(vec.as_slice().is_empty());
+        assert!(vec.as_mut_slice().is_empty());
+        assert!(vec[..].is_empty());
+        assert_eq!(vec.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_slice_methods() {
+        let mut vec = from_slice(&[5, 3, 8, 1, 4]);
+
+        vec.sort();
+        assert_eq!(vec.as_slice(), [1, 3, 4, 5, 8]);
+        assert_eq!(vec.binary_search(&5), Ok(3));
+        assert_eq!(vec.binary_search(&2), Err(1));
+        assert_eq!(
+            vec.chunks(2).collect::<Vec<_>>(),
+            [&[1, 3][..], &[4, 5], &[8]]
+        );
+        assert_eq!(vec.windows(4).count(), 2);
+        assert!(vec.contains(&4));
+
+        vec.reverse();
+        vec.as_mut_slice()[0] = 0;
+        assert_eq!(vec.as_slice(), [0, 5, 4, 3, 1]);
+    }
+
+    #[test]
+    fn test_index() {
+        let mut vec = from_slice(&[10, 20, 30, 40]);
+
+        assert_eq!(vec[0], 10);
+        assert_eq!(vec[1..3], [20, 30]);
+        assert_eq!(vec[..2], [10, 20]);
+        assert_eq!(vec[2..], [30, 40]);
+        assert_eq!(vec[..=1], [10, 20]);
+        assert_eq!(vec[1..=3], [20, 30, 40]);
+        assert_eq!(vec[..], [10, 20, 30, 40]);
+
+        vec[3] += 2;
+        vec[..2].swap(0, 1);
+        assert_eq!(vec[..], [20, 10, 30, 42]);
+    }
+
+    #[test]
+    #[should_panic(expected = "the len is 3 but the index is 3")]
+    fn test_index_out_of_bounds() {
+        let vec = from_slice(&[1, 2, 3]);
+        let _ = vec[3];
+    }
+
+    #[test]
+    #[should_panic(expected = "range end index 5 out of range for slice of length 3")]
+    fn test_range_out_of_bounds() {
+        let mut vec = from_slice(&[1, 2, 3]);
+        vec[1..5].fill(0);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let mut vec = MyVec::new();
+        assert_eq!(vec.capacity(), usize::MAX);
+
+        for _ in 0..100 {
+            vec.push(());
+        }
+        assert_eq!(vec.get(99), Some(&()));
+        assert_eq!(vec.get(100), None);
+        assert_eq!(vec.iter().count(), 100);
+        assert_eq!(vec.pop(), Some(()));
+        assert_eq!(vec.len(), 99);
+        assert_eq!(vec.into_iter().count(), 99);
+    }
+
+    #[test]
+    fn test_zero_sized_drops() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Marker;
+
+        impl Drop for Marker {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut vec = MyVec::new();
+        for _ in 0..10 {
+            vec.push(Marker);
+        }
+        drop(vec.pop());
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+        drop(vec);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 10);
+
+        let mut vec = MyVec::new();
+        for _ in 0..10 {
+            vec.push(Marker);
+        }
+        let mut iter = vec.into_iter();
+        drop(iter.next());
+        drop(iter.next_back());
+        assert_eq!(DROPS.load(Ordering::SeqCst), 12);
+        drop(iter);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 20);
+    }
+
+    #[test]
+    fn test_grow_large_elements() {
+        // `realloc` takes a size in bytes, so this would overrun the buffer if we passed an element count
+        let mut vec = MyVec::new();
+        for i in 0..1000u64 {
+            vec.push([i; 4]);
+        }
+        assert_eq!(vec.len(), 1000);
+        assert!(vec.iter().enumerate().all(|(i, x)| *x == [i as u64; 4]));
+
+        let mut strings = MyVec::new();
+        for i in 0..100 {
+            strings.push(i.to_string());
+        }
+        assert_eq!(strings[99], "99");
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut vec = MyVec::<u32>::with_capacity(10);
+        assert_eq!(vec.capacity(), 10);
+        assert!(vec.is_empty());
+
+        vec.reserve(5);
+        assert_eq!(vec.capacity(), 10);
+        vec.reserve_exact(11);
+        assert_eq!(vec.capacity(), 11);
+        vec.reserve(12);
+        assert!(vec.capacity() >= 12);
+
+        for i in 0..5 {
+            vec.push(i);
+        }
+        vec.shrink_to(8);
+        assert_eq!(vec.capacity(), 8);
+        vec.shrink_to(1);
+        assert_eq!(vec.capacity(), 5);
+        vec.shrink_to(100);
+        assert_eq!(vec.capacity(), 5);
+        assert_eq!(vec[..], [0, 1, 2, 3, 4]);
+
+        vec.clear();
+        assert!(vec.is_empty());
+        assert_eq!(vec.capacity(), 5);
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 0);
+        vec.push(7);
+        assert_eq!(vec[..], [7]);
+
+        assert_eq!(MyVec::<()>::with_capacity(10).capacity(), usize::MAX);
+    }
+
+    #[test]
+    fn test_growth_policy() {
+        fn capacities<G: GrowthPolicy>() -> Vec<usize> {
+            let mut vec = MyVec::<u64, Global, G>::with_growth_in(Global);
+            let mut capacities = vec![];
+            for i in 0..40 {
+                vec.push(i);
+                if capacities.last() != Some(&vec.capacity()) {
+                    capacities.push(vec.capacity());
+                }
+            }
+            assert!(vec.iter().copied().eq(0..40));
+            capacities
+        }
+
+        assert_eq!(capacities::<Doubling>(), [8, 16, 32, 64]);
+        assert_eq!(capacities::<OneAndAHalf>(), [4, 6, 9, 13, 19, 28, 42]);
+        assert_eq!(capacities::<FixedIncrement<15>>(), [15, 30, 45]);
+        assert_eq!(capacities::<PageAligned>(), [512]);
+
+        // `reserve` goes through the policy too, `reserve_exact` doesn't
+        let mut vec = MyVec::<u8, Global, FixedIncrement<10>>::with_growth_in(Global);
+        vec.reserve(3);
+        assert_eq!(vec.capacity(), 10);
+        vec.reserve_exact(11);
+        assert_eq!(vec.capacity(), 11);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut vec = MyVec::new();
+        for i in 0..10 {
+            vec.push(i.to_string());
+        }
+        vec.truncate(20);
+        assert_eq!(vec.len(), 10);
+        vec.truncate(3);
+        assert_eq!(vec[..], ["0", "1", "2"]);
+        vec.clear();
+        assert_eq!(vec.len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn test_reserve_overflow() {
+        let mut vec = MyVec::<u8>::new();
+        vec.push(1);
+        vec.reserve(usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn test_reserve_too_many_bytes() {
+        // fits in a `usize` as an element count, but not as a byte count
+        MyVec::<u32>::with_capacity(usize::MAX / 2);
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut vec = from_slice(&[1, 2, 3]);
+        vec.insert(0, 0);
+        vec.insert(4, 4);
+        vec.insert(2, 10);
+        assert_eq!(vec[..], [0, 1, 10, 2, 3, 4]);
+
+        assert_eq!(vec.remove(2), 10);
+        assert_eq!(vec.remove(0), 0);
+        assert_eq!(vec.remove(3), 4);
+        assert_eq!(vec[..], [1, 2, 3]);
+
+        assert_eq!(vec.swap_remove(0), 1);
+        assert_eq!(vec[..], [3, 2]);
+        assert_eq!(vec.swap_remove(1), 2);
+        assert_eq!(vec[..], [3]);
+
+        // inserting past the initial capacity has to grow the buffer
+        let mut vec = MyVec::new();
+        for i in 0..20 {
+            vec.insert(0, i);
+        }
+        assert_eq!(vec[0], 19);
+        assert_eq!(vec[19], 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index (is 4) should be <= len (is 3)")]
+    fn test_insert_out_of_bounds() {
+        from_slice(&[1, 2, 3]).insert(4, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "removal index (is 3) should be < len (is 3)")]
+    fn test_remove_out_of_bounds() {
+        from_slice(&[1, 2, 3]).remove(3);
+    }
+
+    #[test]
+    fn test_retain() {
+        let counter = test_utils::DropCounter::new();
+        let mut vec = tracked(&counter, &[1, 2, 3, 4, 5, 6]);
+        vec.retain(|x| x.value % 2 == 0);
+        assert_eq!(values(&vec), [2, 4, 6]);
+        assert_eq!(counter.drops(), 3);
+
+        vec.retain_mut(|x| {
+            x.value *= 10;
+            x.value != 40
+        });
+        assert_eq!(values(&vec), [20, 60]);
+        drop(vec);
+        assert_eq!(counter.drops(), 6);
+    }
+
+    #[test]
+    fn test_retain_panicking_predicate() {
+        let counter = test_utils::DropCounter::new();
+        let mut vec = tracked(&counter, &[1, 2, 3, 4, 5]);
+
+        test_utils::assert_panics(|| {
+            vec.retain(|x| {
+                if x.value == 4 {
+                    panic!("predicate panicked");
+                }
+                x.value != 2
+            })
+        });
+
+        // 2 was removed before the panic, and everything from 4 onwards is kept as-is
+        assert_eq!(values(&vec), [1, 3, 4, 5]);
+        assert_eq!(counter.drops(), 1);
+        drop(vec);
+        assert_eq!(counter.drops(), 5);
+    }
+
+    #[test]
+    fn test_retain_panicking_drop() {
+        let counter = test_utils::DropCounter::new();
+        let mut vec = MyVec::new();
+        vec.push(counter.track(1));
+        vec.push(counter.track_panicking(2));
+        vec.push(counter.track(3));
+        vec.push(counter.track(4));
+
+        test_utils::assert_panics(|| vec.retain(|x| x.value % 2 == 1));
+
+        assert_eq!(values(&vec), [1, 3, 4]);
+        assert_eq!(counter.drops(), 1);
+        drop(vec);
+        assert_eq!(counter.drops(), 4);
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut vec = from_slice(&[1, 1, 2, 3, 3, 3, 1, 4, 4]);
+        vec.dedup();
+        assert_eq!(vec[..], [1, 2, 3, 1, 4]);
+
+        let mut vec = from_slice(&[10, 11, 20, 25, 31]);
+        vec.dedup_by_key(|x| *x / 10);
+        assert_eq!(vec[..], [10, 20, 31]);
+
+        let counter = test_utils::DropCounter::new();
+        let mut vec = tracked(&counter, &[1, 1, 1, 2, 2]);
+        vec.dedup();
+        assert_eq!(values(&vec), [1, 2]);
+        assert_eq!(counter.drops(), 3);
+    }
+
+    #[test]
+    fn test_dedup_panicking() {
+        let counter = test_utils::DropCounter::new();
+        let mut vec = tracked(&counter, &[1, 1, 2, 2, 3, 3]);
+
+        test_utils::assert_panics(|| {
+            vec.dedup_by(|a, b| {
+                if a.value == 3 {
+                    panic!("same_bucket panicked");
+                }
+                a.value == b.value
+            })
+        });
+
+        assert_eq!(values(&vec), [1, 2, 3, 3]);
+        assert_eq!(counter.drops(), 2);
+        drop(vec);
+        assert_eq!(counter.drops(), 6);
+    }
+
+    #[test]
+    fn test_split_off_append() {
+        let mut vec = from_slice(&[1, 2, 3, 4, 5]);
+        let mut tail = vec.split_off(2);
+        assert_eq!(vec[..], [1, 2]);
+        assert_eq!(tail[..], [3, 4, 5]);
+        assert!(vec.split_off(2).is_empty());
+
+        vec.append(&mut tail);
+        assert_eq!(vec[..], [1, 2, 3, 4, 5]);
+        assert!(tail.is_empty());
+
+        vec.extend_from_slice(&[6, 7, 8, 9, 10]);
+        assert_eq!(vec.len(), 10);
+        assert_eq!(vec[5..], [6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_extend_from_slice_panicking_clone() {
+        struct PanicOnClone(i32);
+
+        impl Clone for PanicOnClone {
+            fn clone(&self) -> Self {
+                if self.0 == 3 {
+                    panic!("clone panicked");
+                }
+                Self(self.0)
+            }
+        }
+
+        let source = [1, 2, 3, 4].map(PanicOnClone);
+        let mut vec = MyVec::new();
+        test_utils::assert_panics(|| vec.extend_from_slice(&source));
+        assert_eq!(vec.iter().map(|x| x.0).collect::<Vec<_>>(), [1, 2]);
+    }
 }
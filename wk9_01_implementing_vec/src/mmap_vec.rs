@@ -0,0 +1,545 @@
+//! A vector that lives in a file, through `mmap`.
+
+use std::ffi::{c_int, c_void};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::ptr::NonNull;
+
+use crate::PageAligned;
+
+// just the bits of `<sys/mman.h>` we need (values from Linux)
+const PROT_READ: c_int = 0x1;
+const PROT_WRITE: c_int = 0x2;
+const MAP_SHARED: c_int = 0x01;
+const MREMAP_MAYMOVE: c_int = 1;
+const MS_SYNC: c_int = 4;
+const MAP_FAILED: *mut c_void = !0 as *mut c_void;
+
+#[link(name = "c")]
+extern "C" {
+    fn mmap(
+        addr: *mut c_void,
+        length: usize,
+        prot: c_int,
+        flags: c_int,
+        fd: c_int,
+        offset: i64,
+    ) -> *mut c_void;
+    fn mremap(
+        old_address: *mut c_void,
+        old_size: usize,
+        new_size: usize,
+        flags: c_int,
+        ...
+    ) -> *mut c_void;
+    fn msync(addr: *mut c_void, length: usize, flags: c_int) -> c_int;
+    fn munmap(addr: *mut c_void, length: usize) -> c_int;
+}
+
+/// Types that are nothing but bytes: any bit pattern is a valid value, there's no padding, and
+/// there are no pointers or anything else that would mean nothing after a restart.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` (or `#[repr(transparent)]`) structs with no padding whose
+/// fields are all `Pod`, or primitives like the ones it's implemented for here.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($t:ty),*) => {
+        $(
+            // SAFETY: every bit pattern is a valid number
+            unsafe impl Pod for $t {}
+        )*
+    };
+}
+
+impl_pod! { u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64 }
+
+// SAFETY: arrays have no padding between elements
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// What's at the start of every file, so we can tell whether it holds what we think it does.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Header {
+    magic: [u8; 8],
+    elem_size: u64,
+    elem_align: u64,
+    len: u64,
+}
+
+const MAGIC: [u8; 8] = *b"MYVECMAP";
+
+/// Where the elements start. Bigger than the header so any sensible alignment works, since the
+/// mapping itself starts on a page boundary.
+const DATA_OFFSET: usize = 64;
+
+/// A vector of plain-old-data stored in a file, like a `MyVec<T>` that survives restarts.
+///
+/// The file is mapped into memory with `mmap`, so the elements are just memory to us and the
+/// kernel writes them back to disk in the background. [`MmapVec::flush`] waits for that to be
+/// done. Growing extends the file and the mapping together (with `mremap`), doubling like `MyVec`.
+///
+/// The file starts with a header saying how big the elements are and how many there are, and
+/// [`MmapVec::open`] refuses files whose header doesn't match `T`.
+///
+/// Nothing stops another `MmapVec`, or another process, changing the file underneath us, which is
+/// why [`MmapVec::create`] and [`MmapVec::open`] are `unsafe`: the caller has to promise the file
+/// is ours alone while it's open.
+pub struct MmapVec<T: Pod> {
+    file: File,
+    // the whole file: header, elements, and spare capacity
+    map: NonNull<u8>,
+    map_len: usize,
+    _marker: PhantomData<T>,
+}
+
+// SAFETY: we own the mapping like `MyVec` owns its buffer
+unsafe impl<T: Pod + Send> Send for MmapVec<T> {}
+unsafe impl<T: Pod + Sync> Sync for MmapVec<T> {}
+
+impl<T: Pod> MmapVec<T> {
+    /// Creates a new, empty vector in a file at `path`, replacing whatever was there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is zero-sized or needs more alignment than 64.
+    ///
+    /// # Safety
+    ///
+    /// While the vector is alive, nothing else may map, write or truncate the file: not another
+    /// `MmapVec` (in this process or any other), and not anything else. The elements are handed
+    /// out as ordinary `&[T]`s and `&mut [T]`s, so a second mapping would mean two `&mut`s to the
+    /// same memory, and shrinking the file under a mapping makes touching it crash with `SIGBUS`.
+    pub unsafe fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::check_type();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+
+        // start with one page
+        let map_len = PageAligned::PAGE_SIZE.max(DATA_OFFSET + size_of::<T>());
+        file.set_len(map_len as u64)?;
+        let mut vec = Self::map(file, map_len)?;
+        *vec.header_mut() = Header {
+            magic: MAGIC,
+            elem_size: size_of::<T>() as u64,
+            elem_align: align_of::<T>() as u64,
+            len: 0,
+        };
+        Ok(vec)
+    }
+
+    /// Opens a vector that was made by [`MmapVec::create`].
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the file doesn't start with our header, or was
+    /// made for a different type, or is too short for the length it says it has.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is zero-sized or needs more alignment than 64.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`MmapVec::create`]: nothing else may map, write or truncate the file while
+    /// the vector is alive.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::check_type();
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+
+        let file_len = file.metadata()?.len();
+        if file_len < DATA_OFFSET as u64 {
+            return Err(invalid_data("file is too short to have a header"));
+        }
+        let map_len = usize::try_from(file_len)
+            .map_err(|_| invalid_data("file is too big to map into memory"))?;
+
+        let vec = Self::map(file, map_len)?;
+        let header = *vec.header();
+        if header.magic != MAGIC {
+            return Err(invalid_data(
+                "file doesn't start with the MmapVec magic number",
+            ));
+        }
+        if header.elem_size != size_of::<T>() as u64 || header.elem_align != align_of::<T>() as u64
+        {
+            return Err(invalid_data(&format!(
+                "file holds elements of size {} and alignment {}, not {} and {}",
+                header.elem_size,
+                header.elem_align,
+                size_of::<T>(),
+                align_of::<T>(),
+            )));
+        }
+        if header.len > vec.capacity() as u64 {
+            return Err(invalid_data(&format!(
+                "file says it has {} elements, but only has room for {}",
+                header.len,
+                vec.capacity(),
+            )));
+        }
+        Ok(vec)
+    }
+
+    fn check_type() {
+        assert!(
+            size_of::<T>() != 0,
+            "MmapVec doesn't support zero-sized types"
+        );
+        assert!(
+            align_of::<T>() <= DATA_OFFSET,
+            "MmapVec doesn't support alignments over {DATA_OFFSET}"
+        );
+    }
+
+    fn map(file: File, map_len: usize) -> io::Result<Self> {
+        // SAFETY: we're asking for a fresh mapping of a file we have open for reading and writing
+        let ptr = unsafe {
+            mmap(
+                core::ptr::null_mut(),
+                map_len,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            file,
+            // SAFETY: `mmap` never hands out null when it succeeds
+            map: unsafe { NonNull::new_unchecked(ptr.cast()) },
+            map_len,
+            _marker: PhantomData,
+        })
+    }
+
+    fn header(&self) -> &Header {
+        // SAFETY: the mapping is page aligned and always at least `DATA_OFFSET` long
+        unsafe { &*self.map.as_ptr().cast::<Header>() }
+    }
+
+    fn header_mut(&mut self) -> &mut Header {
+        // SAFETY: same as `header`
+        unsafe { &mut *self.map.as_ptr().cast::<Header>() }
+    }
+
+    fn data(&self) -> *mut T {
+        // SAFETY: the data starts inside the mapping, aligned since `T`'s alignment divides 64
+        unsafe { self.map.as_ptr().add(DATA_OFFSET).cast() }
+    }
+
+    pub fn len(&self) -> usize {
+        // it fit in memory when it was pushed
+        self.header().len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many elements fit in the file before it has to grow.
+    pub fn capacity(&self) -> usize {
+        (self.map_len - DATA_OFFSET) / size_of::<T>()
+    }
+
+    /// Appends `value`, growing the file if it's full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file can't grow, e.g. because the disk is full. Use [`MmapVec::try_push`] to
+    /// get the error instead.
+    pub fn push(&mut self, value: T) {
+        if let Err(error) = self.try_push(value) {
+            panic!("couldn't grow MmapVec: {error}");
+        }
+    }
+
+    /// Like [`MmapVec::push`], but returns an error if the file can't grow.
+    pub fn try_push(&mut self, value: T) -> io::Result<()> {
+        let len = self.len();
+        if len == self.capacity() {
+            self.reserve(1)?;
+        }
+        // SAFETY: there's room for it, and `T: Pod` can be written over anything
+        unsafe { self.data().add(len).write(value) };
+        self.header_mut().len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let value = *self.last()?;
+        self.header_mut().len -= 1;
+        Some(value)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    pub fn clear(&mut self) {
+        self.header_mut().len = 0;
+    }
+
+    pub fn extend_from_slice(&mut self, values: &[T]) -> io::Result<()> {
+        self.reserve(values.len())?;
+        let len = self.len();
+        // SAFETY: we just made room, and `values` can't be in our mapping since we're borrowed
+        //         mutably
+        unsafe {
+            core::ptr::copy_nonoverlapping(values.as_ptr(), self.data().add(len), values.len())
+        };
+        self.header_mut().len += values.len() as u64;
+        Ok(())
+    }
+
+    /// Makes sure there's room for `additional` more elements, growing the file (at least
+    /// doubling it) if there isn't.
+    pub fn reserve(&mut self, additional: usize) -> io::Result<()> {
+        let required = self
+            .len()
+            .checked_add(additional)
+            .ok_or_else(|| io::Error::other("capacity overflow"))?;
+        if required <= self.capacity() {
+            return Ok(());
+        }
+
+        let new_capacity = required.max(self.capacity() * 2);
+        let new_len = new_capacity
+            .checked_mul(size_of::<T>())
+            .and_then(|bytes| bytes.checked_add(DATA_OFFSET))
+            .ok_or_else(|| io::Error::other("capacity overflow"))?;
+
+        // the file has to be big enough before the new part of the mapping is touched
+        self.file.set_len(new_len as u64)?;
+        // SAFETY: `map` and `map_len` describe our current mapping, and `MREMAP_MAYMOVE` means
+        //         we take whatever address we're given. Nothing borrows into the old one since we
+        //         have `&mut self`
+        let ptr = unsafe {
+            mremap(
+                self.map.as_ptr().cast(),
+                self.map_len,
+                new_len,
+                MREMAP_MAYMOVE,
+            )
+        };
+        if ptr == MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `mremap` never hands out null when it succeeds
+        self.map = unsafe { NonNull::new_unchecked(ptr.cast()) };
+        self.map_len = new_len;
+        Ok(())
+    }
+
+    /// Waits until everything written so far (including the length) is on disk.
+    pub fn flush(&self) -> io::Result<()> {
+        // SAFETY: it's our mapping
+        if unsafe { msync(self.map.as_ptr().cast(), self.map_len, MS_SYNC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements have all been written, and any bytes are a valid `T`
+        unsafe { core::slice::from_raw_parts(self.data(), self.len()) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: same as `as_slice`, and we have unique access through `&mut self`
+        unsafe { core::slice::from_raw_parts_mut(self.data(), self.len()) }
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl<T: Pod> Drop for MmapVec<T> {
+    fn drop(&mut self) {
+        // the kernel still writes everything back after this, we just stop looking at it
+        // SAFETY: it's our mapping, and nothing can borrow from it any more
+        unsafe { munmap(self.map.as_ptr().cast(), self.map_len) };
+    }
+}
+
+impl<T: Pod> Deref for MmapVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: Pod> DerefMut for MmapVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: Pod + fmt::Debug> fmt::Debug for MmapVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// A file in the temp directory that's deleted afterwards.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("mmap_vec_{}_{name}", std::process::id()));
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn create<T: Pod>(file: &TempFile) -> io::Result<MmapVec<T>> {
+        // SAFETY: every test has its own file, and only ever has one vector open on it at a time
+        unsafe { MmapVec::create(&file.0) }
+    }
+
+    fn open<T: Pod>(file: &TempFile) -> io::Result<MmapVec<T>> {
+        // SAFETY: same as `create`
+        unsafe { MmapVec::open(&file.0) }
+    }
+
+    #[test]
+    fn test_push_and_reopen() {
+        let file = TempFile::new("reopen");
+        {
+            let mut vec = create::<u64>(&file).unwrap();
+            assert!(vec.is_empty());
+            for i in 0..10 {
+                vec.push(i * i);
+            }
+            assert_eq!(vec.get(3), Some(&9));
+            assert_eq!(vec.get(10), None);
+            assert_eq!(vec.pop(), Some(81));
+            vec.flush().unwrap();
+        }
+
+        let mut vec = open::<u64>(&file).unwrap();
+        assert_eq!(vec.len(), 9);
+        assert_eq!(&vec[..3], [0, 1, 4]);
+        vec[0] = 100;
+        vec.push(7);
+        drop(vec);
+
+        let vec = open::<u64>(&file).unwrap();
+        assert_eq!(vec.first(), Some(&100));
+        assert_eq!(vec.last(), Some(&7));
+        assert_eq!(vec.len(), 10);
+    }
+
+    #[test]
+    fn test_grows_past_first_page() {
+        let file = TempFile::new("grow");
+        let mut vec = create::<[u32; 3]>(&file).unwrap();
+        let first_capacity = vec.capacity();
+        for i in 0..10_000 {
+            vec.push([i, i + 1, i + 2]);
+        }
+        assert!(vec.capacity() >= 10_000 && vec.capacity() > first_capacity);
+        vec.extend_from_slice(&[[7; 3]; 100]).unwrap();
+        drop(vec);
+
+        let vec = open::<[u32; 3]>(&file).unwrap();
+        assert_eq!(vec.len(), 10_100);
+        assert!(vec[..10_000]
+            .iter()
+            .enumerate()
+            .all(|(i, &x)| x == [i as u32, i as u32 + 1, i as u32 + 2]));
+        assert_eq!(vec[10_050], [7; 3]);
+    }
+
+    #[test]
+    fn test_rejects_other_types() {
+        let file = TempFile::new("types");
+        let mut vec = create::<u32>(&file).unwrap();
+        vec.push(1);
+        drop(vec);
+
+        let error = open::<u64>(&file).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        // same size, different alignment
+        let error = open::<[u8; 4]>(&file).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(open::<i32>(&file).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_bad_headers() {
+        let file = TempFile::new("headers");
+        assert_eq!(
+            open::<u8>(&file).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        std::fs::write(&file.0, b"short").unwrap();
+        let error = open::<u8>(&file).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        std::fs::write(&file.0, [0xab; 4096]).unwrap();
+        let error = open::<u8>(&file).unwrap_err();
+        assert!(error.to_string().contains("magic"), "{error}");
+
+        // a good header, but a length that's longer than the file
+        drop(create::<u16>(&file).unwrap());
+        let mut bytes = std::fs::read(&file.0).unwrap();
+        bytes[24..32].copy_from_slice(&1_000_000u64.to_ne_bytes());
+        std::fs::write(&file.0, bytes).unwrap();
+        let error = open::<u16>(&file).unwrap_err();
+        assert!(error.to_string().contains("room"), "{error}");
+    }
+
+    #[test]
+    fn test_custom_record() {
+        #[repr(C)]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Reading {
+            timestamp: u64,
+            sensor: u32,
+            value: f32,
+        }
+
+        // SAFETY: `repr(C)`, all `Pod` fields, and 8 + 4 + 4 leaves no padding
+        unsafe impl Pod for Reading {}
+
+        let file = TempFile::new("record");
+        let reading = Reading {
+            timestamp: 1_700_000_000,
+            sensor: 3,
+            value: 21.5,
+        };
+        let mut vec = create(&file).unwrap();
+        vec.push(reading);
+        drop(vec);
+        let vec = open::<Reading>(&file).unwrap();
+        assert_eq!(vec.as_slice(), [reading]);
+        assert_eq!(format!("{vec:?}").matches("sensor: 3").count(), 1);
+    }
+}
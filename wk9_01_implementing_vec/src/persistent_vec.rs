@@ -0,0 +1,619 @@
+//! An immutable vector where every update makes a new version and keeps the old one.
+
+use alloc::rc::Rc;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::{Deref, Index};
+
+use crate::MyVec;
+
+const BITS: u32 = 5;
+/// How many children a branch has, and how many elements a leaf holds.
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+/// Which reference-counted pointer a [`PersistentVec`] shares its nodes through.
+pub trait SharedPointer {
+    type Ptr<U>: Clone + Deref<Target = U>;
+
+    fn new<U>(value: U) -> Self::Ptr<U>;
+
+    /// A mutable reference to the value, cloning it first if anything else points at it, like
+    /// `Rc::make_mut`.
+    fn make_mut<U: Clone>(ptr: &mut Self::Ptr<U>) -> &mut U;
+}
+
+/// Shares nodes with `Rc`. The default, and the quickest, but the vector can't leave its thread.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RcPointer;
+
+/// Shares nodes with `Arc`, so versions can be sent to (and shared with) other threads.
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ArcPointer;
+
+impl SharedPointer for RcPointer {
+    type Ptr<U> = Rc<U>;
+
+    fn new<U>(value: U) -> Rc<U> {
+        Rc::new(value)
+    }
+
+    fn make_mut<U: Clone>(ptr: &mut Rc<U>) -> &mut U {
+        Rc::make_mut(ptr)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl SharedPointer for ArcPointer {
+    type Ptr<U> = Arc<U>;
+
+    fn new<U>(value: U) -> Arc<U> {
+        Arc::new(value)
+    }
+
+    fn make_mut<U: Clone>(ptr: &mut Arc<U>) -> &mut U {
+        Arc::make_mut(ptr)
+    }
+}
+
+enum Node<T, P: SharedPointer> {
+    Branch(MyVec<P::Ptr<Node<T, P>>>),
+    Leaf(MyVec<T>),
+}
+
+impl<T, P: SharedPointer> Node<T, P> {
+    /// An empty node for the level that's `shift` from the bottom.
+    fn empty(shift: u32) -> Self {
+        if shift == 0 {
+            Node::Leaf(MyVec::new())
+        } else {
+            Node::Branch(MyVec::new())
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Node::Branch(children) => children.is_empty(),
+            Node::Leaf(values) => values.is_empty(),
+        }
+    }
+}
+
+impl<T: Clone, P: SharedPointer> Clone for Node<T, P> {
+    fn clone(&self) -> Self {
+        match self {
+            Node::Branch(children) => Node::Branch(children.clone()),
+            Node::Leaf(values) => Node::Leaf(values.clone()),
+        }
+    }
+}
+
+/// A persistent vector: [`push`](PersistentVec::push), [`set`](PersistentVec::set) and
+/// [`pop`](PersistentVec::pop) leave it alone and return a new version instead, and every version
+/// stays usable. Handy for undo histories and snapshots.
+///
+/// It's a tree where every branch has up to 32 children and the elements sit in leaves of 32, so
+/// index `i` is found by reading `i` five bits at a time. An update copies only the nodes on the
+/// path to the element it changes (at most 7 for a billion elements), and the new version shares
+/// every other node with the old one, so updates are O(log32 n) and cloning is O(1).
+///
+/// Nodes are shared with `Rc` by default, or `Arc` with [`ArcPointer`] (see
+/// [`PersistentVec::with_pointer`]). For building one up from lots of updates in a row, use a
+/// [`TransientVec`].
+///
+/// ```
+/// use wk9_01_implementing_vec::persistent_vec::PersistentVec;
+///
+/// let empty = PersistentVec::new();
+/// let one = empty.push("a");
+/// let two = one.push("b");
+/// let changed = two.set(0, "z");
+///
+/// assert!(empty.is_empty());
+/// assert_eq!(one, ["a"]);
+/// assert_eq!(two, ["a", "b"]);
+/// assert_eq!(changed, ["z", "b"]);
+/// ```
+pub struct PersistentVec<T, P: SharedPointer = RcPointer> {
+    root: P::Ptr<Node<T, P>>,
+    // how far to shift an index right to pick the root's child; 0 when the root is a leaf
+    shift: u32,
+    len: usize,
+}
+
+impl<T> PersistentVec<T> {
+    pub fn new() -> Self {
+        Self::with_pointer()
+    }
+}
+
+impl<T, P: SharedPointer> PersistentVec<T, P> {
+    /// Creates an empty vector that shares its nodes through `P`, e.g.
+    /// `PersistentVec::<T, ArcPointer>::with_pointer()`.
+    pub fn with_pointer() -> Self {
+        Self {
+            root: P::new(Node::empty(0)),
+            shift: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        Some(&self.leaf_for(index)[index & MASK])
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    /// The leaf that `index` is in.
+    fn leaf_for(&self, index: usize) -> &[T] {
+        let mut node = &*self.root;
+        let mut shift = self.shift;
+        loop {
+            match node {
+                Node::Branch(children) => {
+                    node = &children[(index >> shift) & MASK];
+                    shift -= BITS;
+                }
+                Node::Leaf(values) => return values,
+            }
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter {
+            vec: self,
+            index: 0,
+            leaf: &[],
+        }
+    }
+}
+
+impl<T: Clone, P: SharedPointer> PersistentVec<T, P> {
+    /// A new version with `value` on the end.
+    pub fn push(&self, value: T) -> Self {
+        let mut new = self.clone();
+        new.push_in_place(value);
+        new
+    }
+
+    /// A new version with the element at `index` replaced by `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&self, index: usize, value: T) -> Self {
+        let mut new = self.clone();
+        new.set_in_place(index, value);
+        new
+    }
+
+    /// A new version without the last element, and the element, or `None` if it's empty.
+    pub fn pop(&self) -> Option<(Self, T)> {
+        let mut new = self.clone();
+        let value = new.pop_in_place()?;
+        Some((new, value))
+    }
+
+    /// A [`TransientVec`] starting from this version, for making lots of changes in a row.
+    pub fn transient(&self) -> TransientVec<T, P> {
+        TransientVec { vec: self.clone() }
+    }
+
+    // the `_in_place` versions copy whatever nodes on the path are shared with other versions
+    // (which is all of them, straight after a `clone`) and change them, leaving everything that
+    // isn't shared alone
+
+    fn push_in_place(&mut self, value: T) {
+        // if the tree's full, put it under a new root one level up
+        if self.len >> self.shift == WIDTH {
+            let mut children = MyVec::new();
+            children.push(self.root.clone());
+            self.root = P::new(Node::Branch(children));
+            self.shift += BITS;
+        }
+        push_into::<T, P>(&mut self.root, self.shift, self.len, value);
+        self.len += 1;
+    }
+
+    fn set_in_place(&mut self, index: usize, value: T) {
+        let len = self.len;
+        assert!(
+            index < len,
+            "index out of bounds: the len is {len} but the index is {index}"
+        );
+
+        let mut node = &mut self.root;
+        let mut shift = self.shift;
+        loop {
+            match P::make_mut(node) {
+                Node::Branch(children) => {
+                    node = &mut children[(index >> shift) & MASK];
+                    shift -= BITS;
+                }
+                Node::Leaf(values) => {
+                    values[index & MASK] = value;
+                    return;
+                }
+            }
+        }
+    }
+
+    fn pop_in_place(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let value = pop_from::<T, P>(&mut self.root);
+        self.len -= 1;
+
+        // a root with only one child is a wasted level
+        while let Node::Branch(children) = &*self.root {
+            if children.len() != 1 {
+                break;
+            }
+            self.root = children[0].clone();
+            self.shift -= BITS;
+        }
+        Some(value)
+    }
+}
+
+/// Adds `value` as element `index` of the tree under `node`, which is `shift` from the bottom.
+fn push_into<T: Clone, P: SharedPointer>(
+    node: &mut P::Ptr<Node<T, P>>,
+    shift: u32,
+    index: usize,
+    value: T,
+) {
+    match P::make_mut(node) {
+        Node::Leaf(values) => values.push(value),
+        Node::Branch(children) => {
+            let child = (index >> shift) & MASK;
+            // the last child is full, so start a new one
+            if child == children.len() {
+                children.push(P::new(Node::empty(shift - BITS)));
+            }
+            push_into::<T, P>(&mut children[child], shift - BITS, index, value);
+        }
+    }
+}
+
+/// Takes the last element out of the tree under `node`, dropping any nodes that leaves empty.
+fn pop_from<T: Clone, P: SharedPointer>(node: &mut P::Ptr<Node<T, P>>) -> T {
+    match P::make_mut(node) {
+        Node::Leaf(values) => values.pop().unwrap(),
+        Node::Branch(children) => {
+            let last = children.last_mut().unwrap();
+            let value = pop_from::<T, P>(last);
+            if last.is_empty() {
+                children.pop();
+            }
+            value
+        }
+    }
+}
+
+/// A [`PersistentVec`] that's changed in place, for building one up (or making a batch of changes)
+/// without making a new version every time. Get one from [`PersistentVec::transient`] or
+/// [`TransientVec::new`], and turn it back with [`TransientVec::persistent`].
+///
+/// Nodes it shares with other versions are copied the first time they're changed, but after that
+/// it owns them and changes them in place, so pushing lots of elements doesn't copy each leaf 32
+/// times over.
+pub struct TransientVec<T, P: SharedPointer = RcPointer> {
+    vec: PersistentVec<T, P>,
+}
+
+impl<T: Clone> TransientVec<T> {
+    pub fn new() -> Self {
+        Self {
+            vec: PersistentVec::new(),
+        }
+    }
+}
+
+impl<T: Clone, P: SharedPointer> TransientVec<T, P> {
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.vec.get(index)
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.vec.push_in_place(value);
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: T) {
+        self.vec.set_in_place(index, value);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.vec.pop_in_place()
+    }
+
+    /// Freezes it into a [`PersistentVec`].
+    pub fn persistent(self) -> PersistentVec<T, P> {
+        self.vec
+    }
+}
+
+impl<T: Clone> Default for TransientVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, P: SharedPointer> Extend<T> for TransientVec<T, P> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T, P: SharedPointer> Clone for PersistentVec<T, P> {
+    /// O(1): the copy shares everything with the original.
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            shift: self.shift,
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Default for PersistentVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug, P: SharedPointer> fmt::Debug for PersistentVec<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, P: SharedPointer> PartialEq for PersistentVec<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, P: SharedPointer> Eq for PersistentVec<T, P> {}
+
+impl<T: PartialEq, P: SharedPointer, const N: usize> PartialEq<[T; N]> for PersistentVec<T, P> {
+    fn eq(&self, other: &[T; N]) -> bool {
+        self.len == N && self.iter().eq(other.iter())
+    }
+}
+
+impl<T, P: SharedPointer> Index<usize> for PersistentVec<T, P> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        let len = self.len;
+        self.get(index).unwrap_or_else(|| {
+            panic!("index out of bounds: the len is {len} but the index is {index}")
+        })
+    }
+}
+
+impl<T: Clone> FromIterator<T> for PersistentVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut transient = TransientVec::new();
+        transient.extend(iter);
+        transient.persistent()
+    }
+}
+
+impl<'a, T, P: SharedPointer> IntoIterator for &'a PersistentVec<T, P> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, P>;
+
+    fn into_iter(self) -> Iter<'a, T, P> {
+        self.iter()
+    }
+}
+
+/// Iterator over a [`PersistentVec`], a leaf at a time.
+pub struct Iter<'a, T, P: SharedPointer> {
+    vec: &'a PersistentVec<T, P>,
+    index: usize,
+    // what's left of the current leaf
+    leaf: &'a [T],
+}
+
+impl<'a, T, P: SharedPointer> Iterator for Iter<'a, T, P> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.leaf.is_empty() {
+            if self.index >= self.vec.len {
+                return None;
+            }
+            // leaves are all full except the last, so this is always the start of one
+            self.leaf = self.vec.leaf_for(self.index);
+        }
+        let (first, rest) = self.leaf.split_first()?;
+        self.leaf = rest;
+        self.index += 1;
+        Some(first)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.vec.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T, P: SharedPointer> ExactSizeIterator for Iter<'_, T, P> {}
+impl<T, P: SharedPointer> FusedIterator for Iter<'_, T, P> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_panics, DropCounter, Rng};
+
+    #[test]
+    fn test_push_get_across_levels() {
+        // enough for a root, a level of branches, and leaves
+        let n = WIDTH * WIDTH * 2 + 5;
+        let vec: PersistentVec<usize> = (0..n).collect();
+        assert_eq!(vec.len(), n);
+        assert_eq!(vec.shift, 2 * BITS);
+        assert!((0..n).all(|i| vec[i] == i));
+        assert_eq!(vec.get(n), None);
+        assert_eq!(vec.last(), Some(&(n - 1)));
+        assert!(vec.iter().copied().eq(0..n));
+        assert_eq!(vec.iter().len(), n);
+        assert_panics(|| vec[n]);
+        assert_panics(|| vec.set(n, 0));
+    }
+
+    #[test]
+    fn test_pop_shrinks_the_tree() {
+        let mut vec: PersistentVec<usize> = (0..WIDTH + 1).collect();
+        assert_eq!(vec.shift, BITS);
+        let (popped, last) = vec.pop().unwrap();
+        assert_eq!(last, WIDTH);
+        assert_eq!(popped.shift, 0);
+        assert_eq!(vec.len(), WIDTH + 1);
+
+        while let Some((smaller, _)) = vec.pop() {
+            vec = smaller;
+        }
+        assert!(vec.is_empty());
+        assert_eq!(vec.shift, 0);
+        assert!(vec.pop().is_none());
+        assert_eq!(vec.push(1), [1]);
+    }
+
+    #[test]
+    fn test_old_versions_are_unchanged() {
+        // do a few thousand random updates, keeping every version along with what it should hold
+        let mut rng = Rng::new(0xfeed);
+        let mut versions = vec![(PersistentVec::new(), Vec::new())];
+        for step in 0..3000 {
+            let (vec, model) = versions[rng.below(versions.len())].clone();
+            let mut model: Vec<usize> = model;
+            let vec = match rng.below(10) {
+                0..=5 => {
+                    model.push(step);
+                    vec.push(step)
+                }
+                6..=8 if !model.is_empty() => {
+                    let index = rng.below(model.len());
+                    model[index] = step;
+                    vec.set(index, step)
+                }
+                _ => match vec.pop() {
+                    Some((vec, value)) => {
+                        assert_eq!(Some(value), model.pop());
+                        vec
+                    }
+                    None => vec,
+                },
+            };
+            versions.push((vec, model));
+        }
+
+        for (vec, model) in &versions {
+            assert_eq!(vec.len(), model.len());
+            assert!(vec.iter().eq(model.iter()));
+        }
+    }
+
+    #[test]
+    fn test_updates_share_untouched_nodes() {
+        let vec: PersistentVec<usize> = (0..WIDTH * 10).collect();
+        let changed = vec.set(5 * WIDTH, 0);
+        // only the changed leaf (and the root above it) got copied
+        for leaf in 0..10 {
+            let same = core::ptr::eq(vec.leaf_for(leaf * WIDTH), changed.leaf_for(leaf * WIDTH));
+            assert_eq!(same, leaf != 5, "leaf {leaf}");
+        }
+        assert!(!Rc::ptr_eq(&vec.root, &changed.root));
+        assert!(Rc::ptr_eq(&vec.root, &vec.clone().root));
+    }
+
+    #[test]
+    fn test_transient() {
+        let base: PersistentVec<i32> = (0..100).collect();
+        let mut transient = base.transient();
+        transient.set(0, -1);
+        transient.push(100);
+        assert_eq!(transient.pop(), Some(100));
+        transient.push(200);
+        assert_eq!(transient.get(0), Some(&-1));
+        assert_eq!(transient.len(), 101);
+        let changed = transient.persistent();
+
+        // `base` didn't see any of that
+        assert!(base.iter().copied().eq(0..100));
+        assert_eq!(changed[0], -1);
+        assert_eq!(changed[100], 200);
+        assert!(changed.iter().skip(1).take(99).copied().eq(1..100));
+    }
+
+    #[test]
+    fn test_drops() {
+        let counter = DropCounter::new();
+        let vec: PersistentVec<_> = (0..100).map(|i| counter.track(i)).collect();
+        let changed = vec.set(0, counter.track(1000));
+        // `changed` copied the first leaf, then overwrote its copy of element 0
+        assert_eq!(counter.drops(), 1);
+        drop(vec);
+        // only the original first leaf goes, since `changed` still shares the rest
+        assert_eq!(counter.drops(), 1 + WIDTH);
+        drop(changed);
+        assert_eq!(counter.drops(), 100 + WIDTH + 1);
+    }
+
+    #[test]
+    fn test_arc_versions_across_threads() {
+        let mut transient = PersistentVec::<String, ArcPointer>::with_pointer().transient();
+        transient.extend((0..1000).map(|i| i.to_string()));
+        let vec = transient.persistent();
+
+        let handles: Vec<_> = (0..4)
+            .map(|thread| {
+                let vec = vec.clone();
+                std::thread::spawn(move || vec.set(thread, "changed".to_string()))
+            })
+            .collect();
+        for (thread, handle) in handles.into_iter().enumerate() {
+            let changed = handle.join().unwrap();
+            assert_eq!(changed[thread], "changed");
+            assert_eq!(vec[thread], thread.to_string());
+        }
+    }
+}
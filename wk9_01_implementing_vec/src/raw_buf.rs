@@ -0,0 +1,373 @@
+//! The raw allocation shared by `MyVec` and the other containers in the crate.
+
+use core::alloc::Layout;
+use core::ptr::NonNull;
+
+use crate::{
+    handle_reserve, Global, GrowthPolicy, MyAllocator, TryReserveError, TryReserveErrorKind,
+};
+
+/// A buffer with room for `capacity` elements of `T`, allocated from `A`.
+///
+/// It only owns the memory: it has no idea which slots hold an initialised `T`, so it never
+/// reads, writes or drops any. That's up to the container using it. Dropping a `RawBuf` just
+/// gives the memory back.
+///
+/// For zero-sized `T` it never allocates, and the capacity is always `usize::MAX`.
+pub(crate) struct RawBuf<T, A: MyAllocator = Global> {
+    // covariant in `T`, same as `MyVec`
+    ptr: NonNull<T>,
+    capacity: usize,
+    alloc: A,
+}
+
+// SAFETY: the buffer is a unique allocation (nothing else points into it), so sending or sharing
+//         it is as safe as sending or sharing the `T`s in it and the allocator it goes back to.
+//         The raw pointer is the only thing stopping the auto traits, and every container built
+//         on a `RawBuf` picks these up
+unsafe impl<T: Send, A: MyAllocator + Send> Send for RawBuf<T, A> {}
+unsafe impl<T: Sync, A: MyAllocator + Sync> Sync for RawBuf<T, A> {}
+
+impl<T> RawBuf<T> {
+    pub(crate) fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T, A: MyAllocator> RawBuf<T, A> {
+    // zero-sized types take up no memory, so we never need to allocate for them,
+    // and we can pretend there's room for as many as a `usize` can count
+    pub(crate) const IS_ZST: bool = core::mem::size_of::<T>() == 0;
+
+    pub(crate) fn new_in(alloc: A) -> Self {
+        Self {
+            // a null pointer can't be turned into a slice, even an empty one,
+            // so we start off with a well-aligned dangling pointer instead
+            ptr: NonNull::dangling(),
+            capacity: if Self::IS_ZST { usize::MAX } else { 0 },
+            alloc,
+        }
+    }
+
+    /// A buffer with room for exactly `capacity` elements.
+    pub(crate) fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        let mut buf = Self::new_in(alloc);
+        buf.reserve_exact(0, capacity);
+        buf
+    }
+
+    pub(crate) fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        let mut buf = Self::new_in(alloc);
+        buf.try_reserve_exact(0, capacity)?;
+        Ok(buf)
+    }
+
+    /// Takes ownership of an existing buffer.
+    ///
+    /// # Safety
+    ///
+    /// Unless `T` is zero-sized or `capacity` is zero, `ptr` must have been allocated from `alloc`
+    /// with the layout `layout_for(capacity)` would give. Otherwise it must be well-aligned (e.g. dangling).
+    pub(crate) unsafe fn from_raw_parts_in(ptr: NonNull<T>, capacity: usize, alloc: A) -> Self {
+        Self {
+            ptr,
+            capacity: if Self::IS_ZST { usize::MAX } else { capacity },
+            alloc,
+        }
+    }
+
+    /// The start of the buffer. Non-null and aligned, even if nothing has been allocated.
+    pub(crate) fn ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Makes sure there's room for `len + additional` elements, growing as `G` says if it has to
+    /// grow at all (so repeated reserves stay amortised O(1) for the policies that double).
+    pub(crate) fn reserve<G: GrowthPolicy>(&mut self, len: usize, additional: usize) {
+        handle_reserve(self.try_reserve::<G>(len, additional));
+    }
+
+    pub(crate) fn reserve_exact(&mut self, len: usize, additional: usize) {
+        handle_reserve(self.try_reserve_exact(len, additional));
+    }
+
+    /// Like `reserve`, but returns an error instead of panicking or aborting.
+    /// The buffer is left unchanged if this fails.
+    pub(crate) fn try_reserve<G: GrowthPolicy>(
+        &mut self,
+        len: usize,
+        additional: usize,
+    ) -> Result<(), TryReserveError> {
+        let required = len
+            .checked_add(additional)
+            .ok_or(TryReserveErrorKind::CapacityOverflow)?;
+
+        if required > self.capacity {
+            self.try_set_capacity(G::grow(self.capacity, required, core::mem::size_of::<T>()))?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn try_reserve_exact(
+        &mut self,
+        len: usize,
+        additional: usize,
+    ) -> Result<(), TryReserveError> {
+        let required = len
+            .checked_add(additional)
+            .ok_or(TryReserveErrorKind::CapacityOverflow)?;
+
+        if required > self.capacity {
+            self.try_set_capacity(required)?;
+        }
+
+        Ok(())
+    }
+
+    /// Grows a full buffer holding `len` elements, for a push that's run out of room.
+    pub(crate) fn grow_one<G: GrowthPolicy>(&mut self, len: usize) {
+        handle_reserve(self.try_grow_one::<G>(len));
+    }
+
+    pub(crate) fn try_grow_one<G: GrowthPolicy>(
+        &mut self,
+        len: usize,
+    ) -> Result<(), TryReserveError> {
+        self.try_reserve::<G>(len, 1)
+    }
+
+    /// Shrinks the buffer to exactly `new_capacity` elements. Does nothing if it's already smaller.
+    ///
+    /// The caller must make sure nothing initialised lives past `new_capacity`.
+    pub(crate) fn shrink_to(&mut self, new_capacity: usize) {
+        if new_capacity < self.capacity {
+            handle_reserve(self.try_set_capacity(new_capacity));
+        }
+    }
+
+    /// Reallocates the buffer to hold exactly `new_capacity` elements (zero-sized types never
+    /// reallocate). On failure the old buffer is untouched.
+    ///
+    /// Anything in the first `min(old, new)` slots is kept, the rest is lost.
+    fn try_set_capacity(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+        if Self::IS_ZST || new_capacity == self.capacity {
+            return Ok(());
+        }
+
+        if new_capacity == 0 {
+            // SAFETY: We allocated `self.ptr` ourself with this layout, and it's non-empty since capacity != 0
+            unsafe { self.deallocate() };
+
+            self.ptr = NonNull::dangling();
+            self.capacity = 0;
+            return Ok(());
+        }
+
+        let new_layout = Self::layout_for(new_capacity)?;
+
+        let new_ptr = if self.capacity == 0 {
+            // `new_layout` has non-zero size, since `T` isn't zero-sized and `new_capacity` isn't zero
+            self.alloc.allocate(new_layout)
+        } else {
+            // SAFETY: We allocated `self.ptr` ourself with a layout for `self.capacity` elements,
+            //         and the new layout is in bytes, non-zero, and no more than `isize::MAX`
+            unsafe {
+                self.alloc.reallocate(
+                    self.ptr.cast(),
+                    Self::layout_for(self.capacity).unwrap(),
+                    new_layout,
+                )
+            }
+        };
+
+        let Some(new_ptr) = new_ptr else {
+            // the old allocation (if any) is still valid, so `self` is left untouched
+            return Err(TryReserveErrorKind::AllocError { layout: new_layout }.into());
+        };
+
+        self.ptr = new_ptr.cast();
+        self.capacity = new_capacity;
+        Ok(())
+    }
+
+    /// Gives the buffer back to the allocator.
+    ///
+    /// # Safety
+    ///
+    /// The buffer must actually be allocated (`T` isn't zero-sized and the capacity isn't zero),
+    /// and mustn't be used again afterwards.
+    unsafe fn deallocate(&mut self) {
+        unsafe {
+            self.alloc
+                .deallocate(self.ptr.cast(), Self::layout_for(self.capacity).unwrap());
+        }
+    }
+
+    /// The layout of a buffer holding `n_elems` elements, or an error if it would be too big.
+    fn layout_for(n_elems: usize) -> Result<Layout, TryReserveError> {
+        let size = core::mem::size_of::<T>()
+            .checked_mul(n_elems)
+            .ok_or(TryReserveErrorKind::CapacityOverflow)?;
+
+        // allocations can't be bigger than `isize::MAX` bytes, or pointer offsets stop working
+        if size > isize::MAX as usize {
+            return Err(TryReserveErrorKind::CapacityOverflow.into());
+        }
+
+        Layout::from_size_align(size, core::mem::align_of::<T>())
+            .map_err(|_| TryReserveErrorKind::CapacityOverflow.into())
+    }
+
+    fn free(&mut self) {
+        if !Self::IS_ZST && self.capacity != 0 {
+            // SAFETY: We allocated `self.ptr` ourself, and we're never going to use it again
+            unsafe { self.deallocate() };
+        }
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
+impl<T, A: MyAllocator> Drop for RawBuf<T, A> {
+    fn drop(&mut self) {
+        self.free();
+    }
+}
+
+// never touches a `T`, so the containers built on it can use `#[may_dangle]` too
+#[cfg(feature = "nightly")]
+unsafe impl<#[may_dangle] T, A: MyAllocator> Drop for RawBuf<T, A> {
+    fn drop(&mut self) {
+        self.free();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::assert_panics;
+    use crate::{CountingAllocator, Doubling, FixedIncrement};
+
+    #[test]
+    fn test_grow() {
+        let alloc = CountingAllocator::new();
+        let mut buf = RawBuf::<u32, _>::new_in(&alloc);
+        assert_eq!(buf.capacity(), 0);
+        assert_eq!(alloc.allocations(), 0);
+
+        buf.grow_one::<Doubling>(0);
+        assert_eq!(buf.capacity(), 8);
+        buf.grow_one::<Doubling>(8);
+        assert_eq!(buf.capacity(), 16);
+
+        // the amortised reserve follows the policy, the exact one doesn't
+        buf.reserve::<Doubling>(16, 1);
+        assert_eq!(buf.capacity(), 32);
+        buf.reserve_exact(32, 1);
+        assert_eq!(buf.capacity(), 33);
+        buf.reserve::<Doubling>(0, 33);
+        assert_eq!(buf.capacity(), 33);
+
+        assert_eq!(alloc.allocations(), 1);
+        assert_eq!(alloc.reallocations(), 3);
+        drop(buf);
+        assert_eq!(alloc.live_allocations(), 0);
+    }
+
+    #[test]
+    fn test_grow_with_policy() {
+        let mut buf = RawBuf::<u32>::new();
+        buf.grow_one::<FixedIncrement<5>>(0);
+        assert_eq!(buf.capacity(), 5);
+        buf.grow_one::<FixedIncrement<5>>(5);
+        assert_eq!(buf.capacity(), 10);
+        buf.reserve::<FixedIncrement<5>>(10, 20);
+        assert_eq!(buf.capacity(), 30);
+    }
+
+    #[test]
+    fn test_contents_survive_growth() {
+        let mut buf = RawBuf::<u64>::with_capacity(3);
+        for i in 0..3 {
+            // SAFETY: `i` is less than the capacity
+            unsafe { buf.ptr().add(i).write(i as u64 * 10) };
+        }
+        buf.grow_one::<Doubling>(3);
+        assert!(buf.capacity() >= 4);
+        // SAFETY: the first three slots were written and growing keeps them
+        let values = unsafe { std::slice::from_raw_parts(buf.ptr(), 3) };
+        assert_eq!(values, [0, 10, 20]);
+    }
+
+    #[test]
+    fn test_shrink() {
+        let alloc = CountingAllocator::new();
+        let mut buf = RawBuf::<u8, _>::with_capacity_in(100, &alloc);
+        buf.shrink_to(200);
+        assert_eq!(buf.capacity(), 100);
+        buf.shrink_to(10);
+        assert_eq!(buf.capacity(), 10);
+        assert_eq!(alloc.bytes_in_use(), 10);
+
+        buf.shrink_to(0);
+        assert_eq!(buf.capacity(), 0);
+        assert_eq!(alloc.live_allocations(), 0);
+        // a fresh allocation once it's grown again
+        buf.grow_one::<Doubling>(0);
+        assert_eq!(alloc.allocations(), 2);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let alloc = CountingAllocator::new();
+        let mut buf = RawBuf::<(), _>::new_in(&alloc);
+        assert_eq!(buf.capacity(), usize::MAX);
+        buf.reserve::<Doubling>(usize::MAX - 1, 1);
+        buf.shrink_to(0);
+        assert_eq!(buf.capacity(), usize::MAX);
+        assert!(buf.try_reserve::<Doubling>(usize::MAX, 1).is_err());
+        drop(buf);
+        assert_eq!(alloc.allocations(), 0);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut buf = RawBuf::<u64>::new();
+        assert_eq!(
+            buf.try_reserve_exact(0, usize::MAX / 4).unwrap_err().kind(),
+            TryReserveErrorKind::CapacityOverflow,
+        );
+        assert_eq!(
+            buf.try_reserve::<Doubling>(usize::MAX, 1)
+                .unwrap_err()
+                .kind(),
+            TryReserveErrorKind::CapacityOverflow,
+        );
+        assert_eq!(buf.capacity(), 0);
+        assert_panics(|| RawBuf::<u64>::new().reserve::<Doubling>(1, usize::MAX));
+    }
+
+    #[test]
+    fn test_auto_traits() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+
+        assert_send::<RawBuf<String>>();
+        assert_sync::<RawBuf<String>>();
+        // `Cell` is `Send` but not `Sync`, and so is `CountingAllocator`, which counts in `Cell`s
+        assert_send::<RawBuf<core::cell::Cell<i32>>>();
+        assert_send::<RawBuf<String, CountingAllocator>>();
+    }
+}
@@ -0,0 +1,589 @@
+//! A generational arena: a `MyVec` of slots with stable, checked keys.
+
+use core::fmt;
+use core::iter::{Enumerate, FusedIterator};
+use core::ops::{Index, IndexMut};
+
+use crate::MyVec;
+
+/// A handle to a value in a [`MySlab`].
+///
+/// It remembers which generation of its slot it was made for, so once the value's removed the
+/// key stops working, even if something new has been put in the same slot since.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key {
+    index: usize,
+    generation: u64,
+}
+
+impl Key {
+    /// Which slot the key points at. Only unique among the live keys.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+enum Slot<T> {
+    Occupied {
+        generation: u64,
+        value: T,
+    },
+    // `generation` is the one the next value in this slot will get
+    Vacant {
+        generation: u64,
+        next_free: Option<usize>,
+    },
+}
+
+impl<T> Slot<T> {
+    fn get(&self, generation: u64) -> Option<&T> {
+        match self {
+            Slot::Occupied {
+                generation: g,
+                value,
+            } if *g == generation => Some(value),
+            _ => None,
+        }
+    }
+
+    fn get_mut(&mut self, generation: u64) -> Option<&mut T> {
+        match self {
+            Slot::Occupied {
+                generation: g,
+                value,
+            } if *g == generation => Some(value),
+            _ => None,
+        }
+    }
+
+    fn occupied(&self) -> Option<(u64, &T)> {
+        match self {
+            Slot::Occupied { generation, value } => Some((*generation, value)),
+            Slot::Vacant { .. } => None,
+        }
+    }
+
+    fn occupied_mut(&mut self) -> Option<(u64, &mut T)> {
+        match self {
+            Slot::Occupied { generation, value } => Some((*generation, value)),
+            Slot::Vacant { .. } => None,
+        }
+    }
+}
+
+/// A collection that hands out a [`Key`] for each value it stores. Keys stay valid until their
+/// value is removed, however much else is inserted or removed, and a stale key gets `None` rather
+/// than some other value. Good for graphs and entity systems, where indices into a `Vec` would
+/// shift around or get reused.
+///
+/// Removed slots go on a free list and get reused by later inserts, with their generation bumped.
+pub struct MySlab<T> {
+    slots: MyVec<Slot<T>>,
+    // the most recently freed slot, which links on to the others through `next_free`
+    free_head: Option<usize>,
+    len: usize,
+}
+
+impl<T> MySlab<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: MyVec::new(),
+            free_head: None,
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: MyVec::with_capacity(capacity),
+            free_head: None,
+            len: 0,
+        }
+    }
+
+    /// How many values are stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// How many values it can hold before it has to reallocate.
+    pub fn capacity(&self) -> usize {
+        // free slots get reused before the vector grows
+        self.slots.capacity()
+    }
+
+    pub fn insert(&mut self, value: T) -> Key {
+        self.insert_with(|_| value)
+    }
+
+    /// Inserts the value `make` returns, which gets to know its own key first (handy for things
+    /// that point at themselves).
+    pub fn insert_with<F: FnOnce(Key) -> T>(&mut self, make: F) -> Key {
+        // work out the key without touching anything, so if `make` panics the slab is unchanged
+        let (key, next_free) = match self.free_head {
+            Some(index) => match self.slots[index] {
+                Slot::Vacant {
+                    generation,
+                    next_free,
+                } => (Key { index, generation }, next_free),
+                Slot::Occupied { .. } => unreachable!("occupied slot on the free list"),
+            },
+            None => {
+                let key = Key {
+                    index: self.slots.len(),
+                    generation: 0,
+                };
+                (key, None)
+            }
+        };
+
+        let slot = Slot::Occupied {
+            generation: key.generation,
+            value: make(key),
+        };
+        if key.index == self.slots.len() {
+            self.slots.push(slot);
+        } else {
+            self.slots[key.index] = slot;
+            self.free_head = next_free;
+        }
+        self.len += 1;
+        key
+    }
+
+    /// Takes the value out, after which `key` (and any copies of it) won't find anything.
+    pub fn remove(&mut self, key: Key) -> Option<T> {
+        self.get(key)?;
+        let vacant = Slot::Vacant {
+            generation: key.generation.wrapping_add(1),
+            next_free: self.free_head,
+        };
+        let Slot::Occupied { value, .. } = core::mem::replace(&mut self.slots[key.index], vacant)
+        else {
+            unreachable!()
+        };
+        self.free_head = Some(key.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn contains(&self, key: Key) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: Key) -> Option<&T> {
+        self.slots.get(key.index)?.get(key.generation)
+    }
+
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        self.slots.get_mut(key.index)?.get_mut(key.generation)
+    }
+
+    /// Mutable references to two values at once, or `None` if either key is stale.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` and `b` are the same key, since that would be two `&mut`s to one value.
+    pub fn get2_mut(&mut self, a: Key, b: Key) -> Option<(&mut T, &mut T)> {
+        assert!(a != b, "get2_mut called with the same key twice");
+        if a.index == b.index {
+            // different generations of one slot, so at most one of them is live
+            return None;
+        }
+
+        let (low, high) = (a.index.min(b.index), a.index.max(b.index));
+        if high >= self.slots.len() {
+            return None;
+        }
+        let (left, right) = self.slots.split_at_mut(high);
+        let (low_slot, high_slot) = (&mut left[low], &mut right[0]);
+        if a.index < b.index {
+            Some((
+                low_slot.get_mut(a.generation)?,
+                high_slot.get_mut(b.generation)?,
+            ))
+        } else {
+            Some((
+                high_slot.get_mut(a.generation)?,
+                low_slot.get_mut(b.generation)?,
+            ))
+        }
+    }
+
+    /// Removes everything. Every key handed out so far stops working.
+    pub fn clear(&mut self) {
+        self.retain(|_, _| false);
+    }
+
+    /// Keeps only the values `keep` returns `true` for.
+    pub fn retain<F: FnMut(Key, &mut T) -> bool>(&mut self, mut keep: F) {
+        for index in 0..self.slots.len() {
+            let Some((generation, value)) = self.slots[index].occupied_mut() else {
+                continue;
+            };
+            if !keep(Key { index, generation }, value) {
+                self.remove(Key { index, generation });
+            }
+        }
+    }
+
+    /// The live values and their keys, in slot order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            slots: self.slots.iter().enumerate(),
+            remaining: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slots: self.slots.iter_mut().enumerate(),
+            remaining: self.len,
+        }
+    }
+
+    pub fn keys(&self) -> impl ExactSizeIterator<Item = Key> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl ExactSizeIterator<Item = &T> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl ExactSizeIterator<Item = &mut T> + '_ {
+        self.iter_mut().map(|(_, value)| value)
+    }
+}
+
+impl<T> Default for MySlab<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Clone for MySlab<T> {
+    fn clone(&self) -> Self {
+        let slots = self
+            .slots
+            .iter()
+            .map(|slot| match slot {
+                Slot::Occupied { generation, value } => Slot::Occupied {
+                    generation: *generation,
+                    value: value.clone(),
+                },
+                Slot::Vacant {
+                    generation,
+                    next_free,
+                } => Slot::Vacant {
+                    generation: *generation,
+                    next_free: *next_free,
+                },
+            })
+            .collect();
+        Self {
+            slots,
+            free_head: self.free_head,
+            len: self.len,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MySlab<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Index<Key> for MySlab<T> {
+    type Output = T;
+
+    /// # Panics
+    ///
+    /// Panics if the key is stale.
+    fn index(&self, key: Key) -> &T {
+        self.get(key).expect("stale slab key")
+    }
+}
+
+impl<T> IndexMut<Key> for MySlab<T> {
+    fn index_mut(&mut self, key: Key) -> &mut T {
+        self.get_mut(key).expect("stale slab key")
+    }
+}
+
+/// Iterator over a [`MySlab`]'s live values and their keys.
+pub struct Iter<'a, T> {
+    slots: Enumerate<crate::Iter<'a, Slot<T>>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Key, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.slots.find_map(|(index, slot)| {
+            let (generation, value) = slot.occupied()?;
+            Some((Key { index, generation }, value))
+        })?;
+        self.remaining -= 1;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            slots: self.slots.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+/// Iterator over a [`MySlab`]'s live values, mutably, and their keys.
+pub struct IterMut<'a, T> {
+    slots: Enumerate<crate::IterMut<'a, Slot<T>>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (Key, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.slots.find_map(|(index, slot)| {
+            let (generation, value) = slot.occupied_mut()?;
+            Some((Key { index, generation }, value))
+        })?;
+        self.remaining -= 1;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+/// Owning iterator over a [`MySlab`]'s live values and their keys.
+pub struct IntoIter<T> {
+    slots: Enumerate<crate::IntoIter<Slot<T>>>,
+    remaining: usize,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = (Key, T);
+
+    fn next(&mut self) -> Option<(Key, T)> {
+        let item = self.slots.find_map(|(index, slot)| match slot {
+            Slot::Occupied { generation, value } => Some((Key { index, generation }, value)),
+            Slot::Vacant { .. } => None,
+        })?;
+        self.remaining -= 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for MySlab<T> {
+    type Item = (Key, T);
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            slots: self.slots.into_iter().enumerate(),
+            remaining: self.len,
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a MySlab<T> {
+    type Item = (Key, &'a T);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut MySlab<T> {
+    type Item = (Key, &'a mut T);
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T> Extend<T> for MySlab<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_panics, DropCounter};
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut slab = MySlab::new();
+        let a = slab.insert("a");
+        let b = slab.insert("b");
+        assert_eq!(slab.len(), 2);
+        assert_eq!(slab.get(a), Some(&"a"));
+        assert_eq!(slab[b], "b");
+
+        *slab.get_mut(a).unwrap() = "A";
+        assert_eq!(slab.remove(a), Some("A"));
+        assert_eq!(slab.remove(a), None);
+        assert_eq!(slab.get(a), None);
+        assert!(!slab.contains(a));
+        assert!(slab.contains(b));
+        assert_eq!(slab.len(), 1);
+    }
+
+    #[test]
+    fn test_stale_key_after_reuse() {
+        let mut slab = MySlab::new();
+        let old = slab.insert(1);
+        slab.remove(old);
+        let new = slab.insert(2);
+
+        // the slot's reused, but the old key doesn't see the new value
+        assert_eq!(new.index(), old.index());
+        assert_ne!(new.generation(), old.generation());
+        assert_eq!(slab.get(old), None);
+        assert_eq!(slab.get(new), Some(&2));
+        assert_panics(|| slab[old]);
+
+        slab.clear();
+        assert!(slab.is_empty());
+        assert_eq!(slab.get(new), None);
+        let newer = slab.insert(3);
+        assert_eq!(slab.get(new), None);
+        assert_eq!(slab[newer], 3);
+    }
+
+    #[test]
+    fn test_free_list_reuses_slots() {
+        let mut slab = MySlab::new();
+        let keys: Vec<_> = (0..10).map(|i| slab.insert(i)).collect();
+        for &key in &keys[2..6] {
+            slab.remove(key);
+        }
+        let capacity = slab.capacity();
+        let reused: Vec<_> = (10..14).map(|i| slab.insert(i)).collect();
+
+        let mut indices: Vec<_> = reused.iter().map(Key::index).collect();
+        indices.sort();
+        assert_eq!(indices, [2, 3, 4, 5]);
+        assert_eq!(slab.capacity(), capacity);
+        assert_eq!(slab.len(), 10);
+    }
+
+    #[test]
+    fn test_insert_with_panicking() {
+        let mut slab = MySlab::new();
+        let a = slab.insert(1);
+        slab.insert(2);
+        slab.remove(a);
+
+        // the freed slot is still first in line after `make` panics
+        assert_panics(|| slab.insert_with(|_| panic!("make panicked")));
+        let b = slab.insert_with(|key| key.index() as i32 * 10);
+        assert_eq!(b.index(), a.index());
+        assert_eq!(slab[b], 0);
+
+        // and with nothing free, the slab doesn't grow a dead slot
+        assert_panics(|| slab.insert_with(|_| panic!("make panicked")));
+        assert_eq!(slab.slots.len(), 2);
+        assert_eq!(slab.insert(3).index(), 2);
+        assert_eq!(slab.len(), 3);
+    }
+
+    #[test]
+    fn test_get2_mut() {
+        let mut slab = MySlab::new();
+        let a = slab.insert(1);
+        let b = slab.insert(2);
+        let c = slab.insert(3);
+
+        let (x, y) = slab.get2_mut(c, a).unwrap();
+        std::mem::swap(x, y);
+        assert_eq!((slab[a], slab[b], slab[c]), (3, 2, 1));
+
+        slab.remove(b);
+        assert!(slab.get2_mut(a, b).is_none());
+        let d = slab.insert(4);
+        // `b` and `d` share a slot
+        assert!(slab.get2_mut(b, d).is_none());
+        assert!(slab.get2_mut(d, a).is_some());
+        assert_panics(move || {
+            slab.get2_mut(a, a);
+        });
+    }
+
+    #[test]
+    fn test_iter_and_retain() {
+        let mut slab: MySlab<i32> = MySlab::new();
+        slab.extend(0..10);
+        let keys: Vec<_> = slab.keys().collect();
+        slab.remove(keys[0]);
+
+        let iter = slab.iter();
+        assert_eq!(iter.len(), 9);
+        assert!(iter.clone().all(|(key, value)| slab[key] == *value));
+
+        for (_, value) in &mut slab {
+            *value *= 10;
+        }
+        slab.retain(|_, value| *value % 20 == 0);
+        assert_eq!(slab.values().copied().collect::<Vec<_>>(), [20, 40, 60, 80]);
+        assert_eq!(slab.get(keys[1]), None);
+        assert_eq!(slab.get(keys[2]), Some(&20));
+
+        let owned: Vec<_> = slab.clone().into_iter().collect();
+        assert_eq!(
+            owned,
+            [(keys[2], 20), (keys[4], 40), (keys[6], 60), (keys[8], 80)]
+        );
+        assert_eq!(format!("{slab:?}").matches("Key").count(), 4);
+    }
+
+    #[test]
+    fn test_drops() {
+        let counter = DropCounter::new();
+        let mut slab = MySlab::new();
+        let keys: Vec<_> = (0..6).map(|i| slab.insert(counter.track(i))).collect();
+        drop(slab.remove(keys[0]));
+        slab.retain(|key, _| key != keys[1]);
+        assert_eq!(counter.drops(), 2);
+        drop(slab);
+        assert_eq!(counter.drops(), 6);
+    }
+}
@@ -0,0 +1,495 @@
+//! A vector that keeps its first few elements inline, only going to the heap once it outgrows them.
+
+use core::fmt;
+use core::iter::FusedIterator;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+
+use crate::{Iter, IterMut, MyVec};
+
+/// Like [`MyVec`], but the first `N` elements live inline (e.g. on the stack), so small vectors
+/// never allocate. Once it holds more than `N` elements it moves everything into a `MyVec`
+/// ("spills") and stays there.
+pub struct MySmallVec<T, const N: usize> {
+    storage: Storage<T, N>,
+}
+
+enum Storage<T, const N: usize> {
+    // only `buf[..len]` is initialised
+    Inline {
+        buf: [MaybeUninit<T>; N],
+        len: usize,
+    },
+    Heap(MyVec<T>),
+}
+
+impl<T, const N: usize> MySmallVec<T, N> {
+    // zero-sized types never need to spill, there's always "room" for another one inline
+    const IS_ZST: bool = core::mem::size_of::<T>() == 0;
+
+    pub fn new() -> Self {
+        Self {
+            storage: Storage::Inline {
+                // an array of `MaybeUninit` doesn't need initialising
+                buf: [const { MaybeUninit::uninit() }; N],
+                len: 0,
+            },
+        }
+    }
+
+    /// Creates an empty vector with room for `capacity` elements, which spills straight away
+    /// if that's more than `N`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        if capacity <= N {
+            Self::new()
+        } else {
+            Self {
+                storage: Storage::Heap(MyVec::with_capacity(capacity)),
+            }
+        }
+    }
+
+    /// How many elements fit inline before the vector spills.
+    pub const fn inline_size(&self) -> usize {
+        N
+    }
+
+    /// Whether the elements have moved to the heap.
+    pub fn spilled(&self) -> bool {
+        matches!(self.storage, Storage::Heap(_))
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Inline { len, .. } => *len,
+            Storage::Heap(vec) => vec.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        match &self.storage {
+            Storage::Inline { .. } if Self::IS_ZST => usize::MAX,
+            Storage::Inline { .. } => N,
+            Storage::Heap(vec) => vec.capacity(),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        match &mut self.storage {
+            Storage::Inline { buf, len } if *len < N || Self::IS_ZST => {
+                // SAFETY: either `len < N` so the slot is inside the array, or `T` is zero-sized
+                //         and writing it doesn't touch memory at all
+                unsafe { buf.as_mut_ptr().cast::<T>().add(*len).write(value) };
+                *len = len.checked_add(1).expect("capacity overflow");
+            }
+            Storage::Inline { .. } => {
+                self.spill(N.saturating_mul(2).max(1));
+                self.push(value);
+            }
+            Storage::Heap(vec) => vec.push(value),
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.storage {
+            Storage::Inline { len: 0, .. } => None,
+            Storage::Inline { buf, len } => {
+                *len -= 1;
+                // SAFETY: `len` was initialised, and is now outside the initialised prefix so it
+                //         won't be read or dropped again. For zero-sized types this reads nothing,
+                //         which is fine since every value of a ZST is the same
+                Some(unsafe { buf.as_ptr().cast::<T>().add(*len).read() })
+            }
+            Storage::Heap(vec) => vec.pop(),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    /// Drops every element from index `len` onwards.
+    pub fn truncate(&mut self, new_len: usize) {
+        match &mut self.storage {
+            Storage::Inline { buf, len } => {
+                if new_len >= *len {
+                    return;
+                }
+                let tail_len = *len - new_len;
+                // shrink first, so if a destructor panics we don't try to drop the tail again
+                *len = new_len;
+                // SAFETY: `new_len..new_len + tail_len` was initialised and is now outside the vector
+                unsafe {
+                    let tail = core::ptr::slice_from_raw_parts_mut(
+                        buf.as_mut_ptr().cast::<T>().add(new_len),
+                        tail_len,
+                    );
+                    tail.drop_in_place();
+                }
+            }
+            Storage::Heap(vec) => vec.truncate(new_len),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        match &self.storage {
+            // SAFETY: the first `len` elements are initialised. Array pointers are never null,
+            //         even for `N == 0` or zero-sized `T`
+            Storage::Inline { buf, len } => unsafe {
+                core::slice::from_raw_parts(buf.as_ptr().cast(), *len)
+            },
+            Storage::Heap(vec) => vec.as_slice(),
+        }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match &mut self.storage {
+            // SAFETY: same as `as_slice`
+            Storage::Inline { buf, len } => unsafe {
+                core::slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), *len)
+            },
+            Storage::Heap(vec) => vec.as_mut_slice(),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self.as_slice())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut::new(self.as_mut_slice())
+    }
+
+    /// Moves the inline elements into a heap buffer with room for `capacity` elements.
+    fn spill(&mut self, capacity: usize) {
+        let Storage::Inline { buf, len } = &mut self.storage else {
+            return;
+        };
+
+        let mut vec = MyVec::with_capacity(capacity.max(*len));
+        // SAFETY: `vec` has room for `len` elements. The elements are moved by copying the bytes,
+        //         and the inline storage is overwritten below without dropping anything (it's all
+        //         `MaybeUninit`), so they're only owned by `vec` from here on
+        unsafe {
+            core::ptr::copy_nonoverlapping(buf.as_ptr().cast::<T>(), vec.buf.ptr(), *len);
+            vec.size = *len;
+        }
+        self.storage = Storage::Heap(vec);
+    }
+}
+
+impl<T, const N: usize> Drop for MySmallVec<T, N> {
+    fn drop(&mut self) {
+        // `MaybeUninit` never drops what it holds, so the inline elements are up to us.
+        // A heap `MyVec` drops its own elements
+        if let Storage::Inline { .. } = self.storage {
+            self.clear();
+        }
+    }
+}
+
+impl<T, const N: usize> Default for MySmallVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for MySmallVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for MySmallVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for MySmallVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for MySmallVec<T, N> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for MySmallVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for MySmallVec<T, N> {}
+
+impl<T, const N: usize> Extend<T> for MySmallVec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let wanted = self.len().saturating_add(iter.size_hint().0);
+        if wanted > self.capacity() {
+            self.spill(wanted);
+        }
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for MySmallVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = Self::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+/// Owning iterator over a [`MySmallVec`].
+pub struct IntoIter<T, const N: usize> {
+    inner: IntoIterInner<T, N>,
+}
+
+enum IntoIterInner<T, const N: usize> {
+    // `buf[start..end]` hasn't been yielded yet
+    Inline {
+        buf: [MaybeUninit<T>; N],
+        start: usize,
+        end: usize,
+    },
+    Heap(crate::IntoIter<T>),
+}
+
+impl<T, const N: usize> IntoIterator for MySmallVec<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> IntoIter<T, N> {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped again, so its storage is only owned once
+        let storage = unsafe { core::ptr::read(&this.storage) };
+
+        let inner = match storage {
+            Storage::Inline { buf, len } => IntoIterInner::Inline {
+                buf,
+                start: 0,
+                end: len,
+            },
+            Storage::Heap(vec) => IntoIterInner::Heap(vec.into_iter()),
+        };
+        IntoIter { inner }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a MySmallVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut MySmallVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match &mut self.inner {
+            IntoIterInner::Inline { buf, start, end } => {
+                if start == end {
+                    return None;
+                }
+                // SAFETY: `start < end`, so this element is initialised and not yet yielded
+                let value = unsafe { buf.as_ptr().cast::<T>().add(*start).read() };
+                *start += 1;
+                Some(value)
+            }
+            IntoIterInner::Heap(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            IntoIterInner::Inline { start, end, .. } => (end - start, Some(end - start)),
+            IntoIterInner::Heap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        match &mut self.inner {
+            IntoIterInner::Inline { buf, start, end } => {
+                if start == end {
+                    return None;
+                }
+                *end -= 1;
+                // SAFETY: same as `next`
+                Some(unsafe { buf.as_ptr().cast::<T>().add(*end).read() })
+            }
+            IntoIterInner::Heap(iter) => iter.next_back(),
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> Drop for IntoIter<T, N> {
+    fn drop(&mut self) {
+        if let IntoIterInner::Inline { .. } = self.inner {
+            for _ in &mut *self {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{DropCounter, Tracked};
+
+    #[test]
+    fn test_stays_inline() {
+        let mut vec = MySmallVec::<i32, 4>::new();
+        assert_eq!(vec.inline_size(), 4);
+        for i in 0..4 {
+            vec.push(i);
+        }
+        assert!(!vec.spilled());
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(vec[..], [0, 1, 2, 3]);
+        assert_eq!(vec.get(3), Some(&3));
+        assert_eq!(vec.get(4), None);
+        assert_eq!(vec.pop(), Some(3));
+        assert_eq!(vec.len(), 3);
+    }
+
+    #[test]
+    fn test_spills() {
+        let mut vec = MySmallVec::<String, 2>::new();
+        for i in 0..10 {
+            vec.push(i.to_string());
+        }
+        assert!(vec.spilled());
+        assert_eq!(vec.len(), 10);
+        assert_eq!(vec[9], "9");
+
+        // it stays spilled even after shrinking back down
+        vec.truncate(1);
+        assert!(vec.spilled());
+        assert_eq!(vec[..], ["0"]);
+
+        assert!(MySmallVec::<u8, 4>::with_capacity(5).spilled());
+        assert!(!MySmallVec::<u8, 4>::with_capacity(4).spilled());
+        assert!(MySmallVec::<u8, 0>::from_iter([1]).spilled());
+    }
+
+    #[test]
+    fn test_slices_and_iterators() {
+        let mut vec: MySmallVec<i32, 8> = [5, 1, 4, 2, 3].into_iter().collect();
+        vec.sort();
+        assert_eq!(vec[..], [1, 2, 3, 4, 5]);
+
+        for x in &mut vec {
+            *x *= 2;
+        }
+        assert_eq!(
+            vec.iter().rev().copied().collect::<Vec<_>>(),
+            [10, 8, 6, 4, 2]
+        );
+
+        let mut iter = vec.clone().into_iter();
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next_back(), Some(10));
+        assert_eq!(iter.len(), 3);
+
+        let spilled: MySmallVec<i32, 2> = (0..5).collect();
+        assert_eq!(
+            spilled.into_iter().rev().collect::<Vec<_>>(),
+            [4, 3, 2, 1, 0]
+        );
+        assert_eq!(format!("{vec:?}"), "[2, 4, 6, 8, 10]");
+    }
+
+    fn tracked<const N: usize>(counter: &DropCounter, count: i32) -> MySmallVec<Tracked, N> {
+        (0..count).map(|i| counter.track(i)).collect()
+    }
+
+    #[test]
+    fn test_drops() {
+        let counter = DropCounter::new();
+
+        drop(tracked::<4>(&counter, 3));
+        assert_eq!(counter.drops(), 3);
+
+        drop(tracked::<4>(&counter, 7));
+        assert_eq!(counter.drops(), 10);
+
+        let mut iter = tracked::<4>(&counter, 4).into_iter();
+        drop(iter.next());
+        drop(iter);
+        assert_eq!(counter.drops(), 14);
+
+        let mut vec = tracked::<4>(&counter, 4);
+        vec.truncate(1);
+        assert_eq!(counter.drops(), 17);
+        vec.push(counter.track(10));
+        drop(vec);
+        assert_eq!(counter.drops(), 19);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mut vec = MySmallVec::<(), 2>::new();
+        for _ in 0..100 {
+            vec.push(());
+        }
+        assert!(!vec.spilled());
+        assert_eq!(vec.len(), 100);
+        assert_eq!(vec.pop(), Some(()));
+        assert_eq!(vec.iter().count(), 99);
+        assert_eq!(vec.into_iter().count(), 99);
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Marker;
+
+        impl Drop for Marker {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut vec = MySmallVec::<Marker, 1>::new();
+        for _ in 0..5 {
+            vec.push(Marker);
+        }
+        drop(vec.pop());
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+        drop(vec);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 5);
+    }
+}
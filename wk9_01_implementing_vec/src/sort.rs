@@ -0,0 +1,543 @@
+//! The sorting and searching behind `MyVec::sort` and friends.
+//!
+//! Everything works on plain slices, and everything takes an `is_less` comparison so the `_by` and
+//! `_by_key` variants are just different closures. Comparisons are user code that can panic at any
+//! point, so nothing here ever leaves an element duplicated or missing: most of it only moves
+//! elements with `swap`, and the one place that doesn't (merging) keeps a guard that puts things
+//! back.
+
+use core::cmp::Ordering;
+use core::mem;
+use core::ptr;
+
+use crate::RawBuf;
+
+/// Runs shorter than this get insertion sorted, which beats anything clever at this size.
+const INSERTION_THRESHOLD: usize = 20;
+
+/// Sorts `v` by swapping each element back past the bigger ones before it. Stable, and fast for
+/// short or nearly sorted slices.
+fn insertion_sort<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) {
+    for i in 1..v.len() {
+        let mut j = i;
+        while j > 0 && is_less(&v[j], &v[j - 1]) {
+            v.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+}
+
+/// A stable merge sort: top-down, with insertion sort for short runs, and a scratch buffer half the
+/// length of `v`.
+pub(crate) fn merge_sort<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) {
+    // zero-sized values are all in the same place, so there's nothing to move
+    if mem::size_of::<T>() == 0 {
+        return;
+    }
+    if v.len() <= INSERTION_THRESHOLD {
+        insertion_sort(v, is_less);
+        return;
+    }
+
+    // only ever holds bitwise copies, so it mustn't drop anything, and `RawBuf` doesn't
+    let scratch = RawBuf::<T>::with_capacity(v.len() / 2);
+    merge_sort_with(v, scratch.ptr(), is_less);
+}
+
+fn merge_sort_with<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], scratch: *mut T, is_less: &mut F) {
+    let len = v.len();
+    if len <= INSERTION_THRESHOLD {
+        insertion_sort(v, is_less);
+        return;
+    }
+
+    let mid = len / 2;
+    merge_sort_with(&mut v[..mid], scratch, is_less);
+    merge_sort_with(&mut v[mid..], scratch, is_less);
+
+    // already in order, which makes sorted input linear
+    if !is_less(&v[mid], &v[mid - 1]) {
+        return;
+    }
+    // SAFETY: `scratch` has room for half of the top-level slice, which is at least `mid`
+    unsafe { merge(v, mid, scratch, is_less) };
+}
+
+/// Merges the sorted runs `v[..mid]` and `v[mid..]`.
+///
+/// # Safety
+///
+/// `scratch` must be valid for writing `mid` elements, and not overlap `v`.
+unsafe fn merge<T, F: FnMut(&T, &T) -> bool>(
+    v: &mut [T],
+    mid: usize,
+    scratch: *mut T,
+    is_less: &mut F,
+) {
+    let v = v.as_mut_ptr_range();
+
+    // move the left run out of the way, and merge back into `v` from the front. The gap between
+    // `hole.dest` and `right` is always exactly as big as what's left of the left run
+    // SAFETY: the caller promises `scratch` has room, and the left run is `mid` long
+    let mut hole = unsafe {
+        ptr::copy_nonoverlapping(v.start, scratch, mid);
+        MergeHole {
+            start: scratch,
+            end: scratch.add(mid),
+            dest: v.start,
+        }
+    };
+    // SAFETY: `mid` is in bounds
+    let mut right = unsafe { v.start.add(mid) };
+
+    while hole.start < hole.end && right < v.end {
+        // SAFETY: both pointers are to live elements inside their runs, and every element is
+        //         copied to `dest` exactly once before the run it came from moves past it
+        unsafe {
+            // only take from the right when it's strictly smaller, so equal elements stay in order
+            let from = if is_less(&*right, &*hole.start) {
+                let from = right;
+                right = right.add(1);
+                from
+            } else {
+                let from = hole.start;
+                hole.start = hole.start.add(1);
+                from
+            };
+            ptr::copy_nonoverlapping(from, hole.dest, 1);
+            hole.dest = hole.dest.add(1);
+        }
+    }
+    // anything left of the right run is already in place, and dropping `hole` moves the rest of
+    // the left run into the gap in front of it (which it also does if `is_less` panics)
+}
+
+/// The part of the left run that hasn't been merged yet, and where it needs to go.
+struct MergeHole<T> {
+    start: *mut T,
+    end: *mut T,
+    dest: *mut T,
+}
+
+impl<T> Drop for MergeHole<T> {
+    fn drop(&mut self) {
+        // SAFETY: `start..end` is in the scratch buffer and `dest` is in the slice, with exactly
+        //         that much room before the unmerged part of the right run
+        unsafe {
+            let len = self.end.offset_from(self.start) as usize;
+            ptr::copy_nonoverlapping(self.start, self.dest, len);
+        }
+    }
+}
+
+/// An unstable introsort: quicksort, falling back to heap sort if the pivots keep being bad, with
+/// pattern-defeating quicksort's trick of lumping together elements equal to an earlier pivot so
+/// lots of duplicates don't make it quadratic.
+pub(crate) fn quicksort<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) {
+    if mem::size_of::<T>() == 0 {
+        return;
+    }
+    quicksort_within(v, is_less, None, 2 * log2(v.len()));
+}
+
+/// Sorts `v`, where every element is at least as big as `pred` (the pivot just before `v`, if
+/// there is one). After `limit` more partitions it gives up and heap sorts.
+fn quicksort_within<'a, T, F: FnMut(&T, &T) -> bool>(
+    mut v: &'a mut [T],
+    is_less: &mut F,
+    mut pred: Option<&'a T>,
+    mut limit: u32,
+) {
+    loop {
+        if v.len() <= INSERTION_THRESHOLD {
+            insertion_sort(v, is_less);
+            return;
+        }
+        if limit == 0 {
+            heapsort(v, is_less);
+            return;
+        }
+        limit -= 1;
+
+        let pivot = choose_pivot(v, is_less);
+
+        // if the pivot's no bigger than `pred` it must be equal to it, the smallest value in `v`,
+        // so there are probably lots of copies: put them all at the front and skip over them
+        if let Some(pred) = pred {
+            if !is_less(pred, &v[pivot]) {
+                let equal = partition_equal(v, pivot, is_less);
+                v = &mut v[equal..];
+                continue;
+            }
+        }
+
+        let mid = partition(v, pivot, is_less);
+        let (left, right) = v.split_at_mut(mid);
+        let (pivot, right) = right.split_first_mut().unwrap();
+
+        // recurse on the left, loop on the right
+        quicksort_within(left, is_less, pred, limit);
+        v = right;
+        pred = Some(pivot);
+    }
+}
+
+/// Picks a pivot index: the median of three spread-out elements.
+fn choose_pivot<T, F: FnMut(&T, &T) -> bool>(v: &[T], is_less: &mut F) -> usize {
+    let len = v.len();
+    let (a, b, c) = (len / 4, len / 2, len / 4 * 3);
+    if is_less(&v[b], &v[a]) {
+        if is_less(&v[c], &v[b]) {
+            b
+        } else if is_less(&v[c], &v[a]) {
+            c
+        } else {
+            a
+        }
+    } else if is_less(&v[c], &v[b]) {
+        if is_less(&v[c], &v[a]) {
+            a
+        } else {
+            c
+        }
+    } else {
+        b
+    }
+}
+
+/// Moves everything smaller than `v[pivot]` in front of it and everything else after it, returning
+/// where the pivot ended up.
+fn partition<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], pivot: usize, is_less: &mut F) -> usize {
+    v.swap(0, pivot);
+    let (pivot, rest) = v.split_first_mut().unwrap();
+
+    // Hoare's scheme: walk in from both ends, and only swap pairs that are both on the wrong side
+    let (mut left, mut right) = (0, rest.len());
+    loop {
+        while left < right && is_less(&rest[left], pivot) {
+            left += 1;
+        }
+        while left < right && !is_less(&rest[right - 1], pivot) {
+            right -= 1;
+        }
+        if left >= right {
+            break;
+        }
+        right -= 1;
+        rest.swap(left, right);
+        left += 1;
+    }
+
+    v.swap(0, left);
+    left
+}
+
+/// Moves everything no bigger than `v[pivot]` to the front, returning how many elements that is
+/// (including the pivot).
+fn partition_equal<T, F: FnMut(&T, &T) -> bool>(
+    v: &mut [T],
+    pivot: usize,
+    is_less: &mut F,
+) -> usize {
+    v.swap(0, pivot);
+    let (pivot, rest) = v.split_first_mut().unwrap();
+    let mut equal = 0;
+    for i in 0..rest.len() {
+        if !is_less(pivot, &rest[i]) {
+            rest.swap(i, equal);
+            equal += 1;
+        }
+    }
+    equal + 1
+}
+
+/// Guaranteed O(n log n), for when quicksort's pivots keep going wrong.
+fn heapsort<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) {
+    for index in (0..v.len() / 2).rev() {
+        sift_down(v, index, is_less);
+    }
+    for end in (1..v.len()).rev() {
+        v.swap(0, end);
+        sift_down(&mut v[..end], 0, is_less);
+    }
+}
+
+/// Moves `v[index]` down the max-heap in `v` until it's bigger than both its children.
+fn sift_down<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], mut index: usize, is_less: &mut F) {
+    loop {
+        let mut child = 2 * index + 1;
+        if child >= v.len() {
+            return;
+        }
+        if child + 1 < v.len() && is_less(&v[child], &v[child + 1]) {
+            child += 1;
+        }
+        if !is_less(&v[index], &v[child]) {
+            return;
+        }
+        v.swap(index, child);
+        index = child;
+    }
+}
+
+/// Quickselect: partitions `v` until `v[index]` is the element that would be there if it were
+/// sorted, with nothing bigger before it and nothing smaller after it.
+pub(crate) fn select_nth<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], index: usize, is_less: &mut F) {
+    if mem::size_of::<T>() == 0 {
+        return;
+    }
+
+    // the part of `v` that `index` is still somewhere in
+    let (mut lo, mut hi) = (0, v.len());
+    let mut limit = 2 * log2(v.len());
+    loop {
+        let part = &mut v[lo..hi];
+        if part.len() <= INSERTION_THRESHOLD {
+            insertion_sort(part, is_less);
+            return;
+        }
+        if limit == 0 {
+            heapsort(part, is_less);
+            return;
+        }
+        limit -= 1;
+
+        let pivot = choose_pivot(part, is_less);
+        let mid = lo + partition(part, pivot, is_less);
+        match index.cmp(&mid) {
+            Ordering::Less => hi = mid,
+            Ordering::Greater => lo = mid + 1,
+            Ordering::Equal => return,
+        }
+    }
+}
+
+/// Binary search on a slice that's sorted according to `compare`. `Ok` is the index of a matching
+/// element (any of them if there are several), `Err` where one could be inserted.
+pub(crate) fn binary_search_by<T, F: FnMut(&T) -> Ordering>(
+    v: &[T],
+    mut compare: F,
+) -> Result<usize, usize> {
+    let (mut lo, mut hi) = (0, v.len());
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match compare(&v[mid]) {
+            Ordering::Less => lo = mid + 1,
+            Ordering::Greater => hi = mid,
+            Ordering::Equal => return Ok(mid),
+        }
+    }
+    Err(lo)
+}
+
+/// The index of the first element `pred` is false for, assuming it's true for some prefix of `v`
+/// and false for the rest.
+pub(crate) fn partition_point<T, P: FnMut(&T) -> bool>(v: &[T], mut pred: P) -> usize {
+    let (mut lo, mut hi) = (0, v.len());
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(&v[mid]) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+/// Roughly log2(len), for the recursion limits.
+fn log2(len: usize) -> u32 {
+    usize::BITS - len.leading_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_panics, DropCounter, Rng};
+    use crate::MyVec;
+
+    /// Inputs of all the shapes that tend to trip sorts up.
+    fn inputs() -> Vec<(&'static str, Vec<i32>)> {
+        let mut rng = Rng::new(0x1234_5678);
+        let mut inputs = Vec::new();
+        for len in [0, 1, 2, 3, 19, 20, 21, 50, 257, 1000, 4096] {
+            let n = len as i32;
+            let random = (0..len).map(|_| rng.next_u64() as i32).collect();
+            let duplicates = (0..len).map(|_| rng.below(4) as i32).collect();
+            inputs.push(("sorted", (0..n).collect()));
+            inputs.push(("reversed", (0..n).rev().collect()));
+            inputs.push(("random", random));
+            inputs.push(("duplicates", duplicates));
+            inputs.push(("all equal", vec![7; len]));
+            inputs.push(("sawtooth", (0..n).map(|i| i % 16).collect()));
+            inputs.push(("organ pipe", (0..n).map(|i| i.min(n - i)).collect()));
+        }
+        inputs
+    }
+
+    #[test]
+    fn test_sorts_match_std() {
+        for (shape, input) in inputs() {
+            let mut expected = input.clone();
+            expected.sort();
+
+            let mut vec = MyVec::from(input.clone());
+            vec.sort();
+            assert_eq!(vec, expected, "sort, {shape}, len {}", input.len());
+
+            let mut vec = MyVec::from(input.clone());
+            vec.sort_unstable();
+            assert_eq!(vec, expected, "sort_unstable, {shape}, len {}", input.len());
+
+            let mut vec = MyVec::from(input.clone());
+            vec.sort_by(|a, b| b.cmp(a));
+            assert!(vec.iter().rev().eq(&expected), "sort_by, {shape}");
+
+            let mut vec = MyVec::from(input.clone());
+            vec.sort_unstable_by_key(|x| x.wrapping_neg());
+            let mut by_key = input.clone();
+            by_key.sort_by_key(|x| x.wrapping_neg());
+            assert_eq!(vec, by_key, "sort_unstable_by_key, {shape}");
+        }
+    }
+
+    #[test]
+    fn test_stable_sorts_are_stable() {
+        for (shape, input) in inputs() {
+            // sort pairs by their first half only, so equal keys have to keep their order
+            let pairs: Vec<_> = input.iter().map(|x| x % 10).zip(0..).collect();
+            let mut expected = pairs.clone();
+            expected.sort_by_key(|pair| pair.0);
+
+            let mut vec = MyVec::from(pairs.clone());
+            vec.sort_by_key(|pair| pair.0);
+            assert_eq!(vec, expected, "sort_by_key, {shape}");
+
+            let mut vec = MyVec::from(pairs.clone());
+            vec.sort_by_cached_key(|pair| pair.0.to_string());
+            let mut cached = pairs;
+            cached.sort_by_cached_key(|pair| pair.0.to_string());
+            assert_eq!(vec, cached, "sort_by_cached_key, {shape}");
+        }
+    }
+
+    #[test]
+    fn test_heapsort() {
+        for (shape, input) in inputs() {
+            let mut expected = input.clone();
+            expected.sort();
+            let mut sorted = input;
+            heapsort(&mut sorted, &mut |a, b| a < b);
+            assert_eq!(sorted, expected, "{shape}");
+        }
+    }
+
+    #[test]
+    fn test_select_nth_unstable() {
+        for (shape, input) in inputs() {
+            let mut expected = input.clone();
+            expected.sort();
+            for index in [
+                0,
+                input.len() / 3,
+                input.len() / 2,
+                input.len().saturating_sub(1),
+            ] {
+                if index >= input.len() {
+                    continue;
+                }
+                let mut vec = MyVec::from(input.clone());
+                let (before, nth, after) = vec.select_nth_unstable(index);
+                assert_eq!(*nth, expected[index], "{shape}, index {index}");
+                assert!(before.iter().all(|x| x <= nth));
+                assert!(after.iter().all(|x| x >= nth));
+            }
+        }
+        assert_panics(|| *MyVec::from([1, 2, 3]).select_nth_unstable(3).1);
+    }
+
+    #[test]
+    fn test_searches_match_std() {
+        let vec = MyVec::from([1, 2, 2, 2, 5, 8, 13]);
+        let std: Vec<_> = vec.iter().copied().collect();
+        for x in 0..15 {
+            let found = vec.binary_search(&x);
+            assert_eq!(found.is_ok(), std.binary_search(&x).is_ok());
+            match found {
+                Ok(index) => assert_eq!(vec[index], x),
+                Err(index) => assert_eq!(index, std.partition_point(|&y| y < x)),
+            }
+            assert_eq!(
+                vec.partition_point(|&y| y < x),
+                std.partition_point(|&y| y < x)
+            );
+            assert_eq!(
+                vec.binary_search_by_key(&(x * 2), |&y| y * 2).is_ok(),
+                std.binary_search(&x).is_ok()
+            );
+        }
+        assert_eq!(MyVec::<i32>::new().binary_search(&1), Err(0));
+        assert_eq!(MyVec::<i32>::new().partition_point(|_| true), 0);
+    }
+
+    #[test]
+    fn test_panicking_comparison() {
+        type Sort = fn(&mut MyVec<crate::test_utils::Tracked>, &mut dyn FnMut() -> bool);
+        let sorts: [(&str, Sort); 3] = [
+            ("sort", |vec, panics| {
+                vec.sort_by(|a, b| {
+                    assert!(!panics());
+                    a.value.cmp(&b.value)
+                })
+            }),
+            ("sort_unstable", |vec, panics| {
+                vec.sort_unstable_by(|a, b| {
+                    assert!(!panics());
+                    a.value.cmp(&b.value)
+                })
+            }),
+            ("select_nth_unstable", |vec, panics| {
+                vec.select_nth_unstable_by(100, |a, b| {
+                    assert!(!panics());
+                    a.value.cmp(&b.value)
+                });
+            }),
+        ];
+
+        let mut rng = Rng::new(99);
+        for (name, sort) in sorts {
+            for panic_after in [0, 1, 10, 100, 400] {
+                let counter = DropCounter::new();
+                let values: Vec<i32> = (0..500).map(|_| rng.below(100) as i32).collect();
+                let mut vec: MyVec<_> = values.iter().map(|&x| counter.track(x)).collect();
+
+                let mut comparisons = 0;
+                let mut panics = || {
+                    comparisons += 1;
+                    comparisons > panic_after
+                };
+                assert_panics(|| sort(&mut vec, &mut panics));
+
+                // every element's still there exactly once, and none were dropped
+                assert_eq!(counter.drops(), 0, "{name} after {panic_after}");
+                let mut left: Vec<_> = vec.iter().map(|t| t.value).collect();
+                let mut original = values;
+                left.sort();
+                original.sort();
+                assert_eq!(left, original, "{name} after {panic_after}");
+                drop(vec);
+                assert_eq!(counter.drops(), 500);
+            }
+        }
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let mut vec = MyVec::from([(); 50]);
+        vec.sort();
+        vec.sort_unstable();
+        assert_eq!(vec.select_nth_unstable(10).1, &());
+        assert_eq!(vec.binary_search(&()), Ok(25));
+        assert_eq!(vec.len(), 50);
+    }
+}
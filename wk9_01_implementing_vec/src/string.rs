@@ -0,0 +1,439 @@
+//! A growable UTF-8 string on top of `MyVec<u8>`.
+
+use core::borrow::Borrow;
+use core::convert::Infallible;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut};
+use core::str::{CharIndices, Chars, FromStr};
+
+use alloc::string::String;
+
+use crate::{FromUtf8Error, MyVec};
+
+/// A growable string, like `String`: a `MyVec<u8>` that always holds valid UTF-8.
+///
+/// Everything that changes the bytes goes through a method that keeps them valid, which is what
+/// makes handing out a `&str` (without checking) sound.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MyString {
+    vec: MyVec<u8>,
+}
+
+impl MyString {
+    pub fn new() -> Self {
+        Self { vec: MyVec::new() }
+    }
+
+    /// Creates an empty string with room for at least `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            vec: MyVec::with_capacity(capacity),
+        }
+    }
+
+    /// Checks that `bytes` are valid UTF-8 and wraps them without copying.
+    ///
+    /// ```
+    /// use wk9_01_implementing_vec::{MyString, MyVec};
+    ///
+    /// let bytes = MyVec::from([b'h', b'i', 0xff]);
+    /// let error = MyString::from_utf8(bytes).unwrap_err();
+    /// assert_eq!(error.valid_up_to(), 2);
+    /// assert_eq!(error.to_string(), "invalid utf-8 sequence [ff] of 1 bytes from index 2");
+    /// ```
+    pub fn from_utf8(bytes: MyVec<u8>) -> Result<Self, FromUtf8Error> {
+        match core::str::from_utf8(&bytes) {
+            Ok(_) => Ok(Self { vec: bytes }),
+            Err(error) => Err(FromUtf8Error { bytes, error }),
+        }
+    }
+
+    /// Wraps `bytes` without checking they're valid UTF-8. Faster than [`MyString::from_utf8`],
+    /// but it's up to the caller to get it right.
+    ///
+    /// ```
+    /// use wk9_01_implementing_vec::{MyString, MyVec};
+    ///
+    /// let sparkle_heart = MyVec::from([240, 159, 146, 150]);
+    ///
+    /// // SAFETY: these bytes are the UTF-8 encoding of 💖
+    /// let sparkle_heart = unsafe { MyString::from_utf8_unchecked(sparkle_heart) };
+    ///
+    /// assert_eq!("💖", sparkle_heart);
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be valid UTF-8. Every other method assumes it is, so getting this wrong is
+    /// undefined behaviour (e.g. `chars` reading past the end of the buffer).
+    pub unsafe fn from_utf8_unchecked(bytes: MyVec<u8>) -> Self {
+        Self { vec: bytes }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // SAFETY: `vec` is always valid UTF-8
+        unsafe { core::str::from_utf8_unchecked(&self.vec) }
+    }
+
+    pub fn as_mut_str(&mut self) -> &mut str {
+        // SAFETY: `vec` is always valid UTF-8, and a `&mut str` can only be changed in ways that keep it so
+        unsafe { core::str::from_utf8_unchecked_mut(&mut self.vec) }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.vec
+    }
+
+    /// Gives back the underlying bytes.
+    pub fn into_bytes(self) -> MyVec<u8> {
+        self.vec
+    }
+
+    /// The length in bytes, not characters.
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.vec.capacity()
+    }
+
+    pub fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn push_str(&mut self, string: &str) {
+        self.vec.extend_from_slice(string.as_bytes());
+    }
+
+    /// Removes the last character and returns it.
+    pub fn pop(&mut self) -> Option<char> {
+        let ch = self.chars().next_back()?;
+        self.vec.truncate(self.len() - ch.len_utf8());
+        Some(ch)
+    }
+
+    /// Inserts `ch` at byte index `index`, moving everything after it along.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is past the end, or isn't on a character boundary.
+    pub fn insert(&mut self, index: usize, ch: char) {
+        self.insert_str(index, ch.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Inserts `string` at byte index `index`, moving everything after it along.
+    ///
+    /// # Panics
+    ///
+    /// Same as [`MyString::insert`].
+    pub fn insert_str(&mut self, index: usize, string: &str) {
+        assert!(
+            self.is_char_boundary(index),
+            "byte index {index} is not a char boundary of a string of length {}",
+            self.len()
+        );
+        // the new bytes are valid UTF-8 and go in between two characters, so the whole thing stays valid
+        self.vec.splice(index..index, string.bytes()).for_each(drop);
+    }
+
+    /// Removes the character starting at byte index `index` and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is at or past the end, or isn't on a character boundary.
+    pub fn remove(&mut self, index: usize) -> char {
+        let Some(ch) = self.get(index..).and_then(|rest| rest.chars().next()) else {
+            panic!(
+                "byte index {index} is not the start of a char in a string of length {}",
+                self.len()
+            );
+        };
+        self.vec.drain(index..index + ch.len_utf8());
+        ch
+    }
+
+    /// Shortens the string to `new_len` bytes. Does nothing if it's already shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` isn't on a character boundary.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len < self.len() {
+            assert!(
+                self.is_char_boundary(new_len),
+                "byte index {new_len} is not a char boundary"
+            );
+            self.vec.truncate(new_len);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.vec.clear();
+    }
+
+    pub fn chars(&self) -> Chars<'_> {
+        self.as_str().chars()
+    }
+
+    /// The characters along with the byte index each one starts at.
+    pub fn char_indices(&self) -> CharIndices<'_> {
+        self.as_str().char_indices()
+    }
+}
+
+impl Deref for MyString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl DerefMut for MyString {
+    fn deref_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl fmt::Display for MyString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for MyString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Write for MyString {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.push_str(string);
+        Ok(())
+    }
+}
+
+impl FromStr for MyString {
+    type Err = Infallible;
+
+    fn from_str(string: &str) -> Result<Self, Infallible> {
+        Ok(Self::from(string))
+    }
+}
+
+impl From<&str> for MyString {
+    fn from(string: &str) -> Self {
+        let mut my_string = Self::with_capacity(string.len());
+        my_string.push_str(string);
+        my_string
+    }
+}
+
+impl From<String> for MyString {
+    /// Takes over the `String`'s buffer without copying.
+    fn from(string: String) -> Self {
+        Self {
+            vec: MyVec::from(string.into_bytes()),
+        }
+    }
+}
+
+impl From<MyString> for String {
+    fn from(string: MyString) -> Self {
+        // SAFETY: a `MyString` is always valid UTF-8
+        unsafe { String::from_utf8_unchecked(string.vec.into()) }
+    }
+}
+
+// a `MyString` hashes like the `str` it derefs to, so `Borrow<str>` lookups in maps work
+impl Hash for MyString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl AsRef<str> for MyString {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl AsRef<[u8]> for MyString {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Borrow<str> for MyString {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl Extend<char> for MyString {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        for ch in iter {
+            self.push(ch);
+        }
+    }
+}
+
+impl<'a> Extend<&'a str> for MyString {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for string in iter {
+            self.push_str(string);
+        }
+    }
+}
+
+impl FromIterator<char> for MyString {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut string = Self::new();
+        string.extend(iter);
+        string
+    }
+}
+
+impl<'a> FromIterator<&'a str> for MyString {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut string = Self::new();
+        string.extend(iter);
+        string
+    }
+}
+
+// compare against the std string types both ways round, like `String` does
+macro_rules! impl_str_eq {
+    ($($other:ty),*) => {
+        $(
+            impl PartialEq<$other> for MyString {
+                fn eq(&self, other: &$other) -> bool {
+                    self.as_str() == &other[..]
+                }
+            }
+
+            impl PartialEq<MyString> for $other {
+                fn eq(&self, other: &MyString) -> bool {
+                    &self[..] == other.as_str()
+                }
+            }
+        )*
+    };
+}
+
+impl_str_eq! { str, &str, String }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::assert_panics;
+
+    #[test]
+    fn test_push_pop() {
+        let mut string = MyString::new();
+        string.push('h');
+        string.push_str("éllo ");
+        string.push('🦀');
+        assert_eq!(string, "héllo 🦀");
+        assert_eq!(string.len(), 11);
+
+        assert_eq!(string.pop(), Some('🦀'));
+        assert_eq!(string.pop(), Some(' '));
+        assert_eq!(string, "héllo");
+        string.clear();
+        assert_eq!(string.pop(), None);
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut string = MyString::from("hllo");
+        string.insert(1, 'é');
+        string.insert_str(0, "¡");
+        string.insert(string.len(), '!');
+        assert_eq!(string, "¡héllo!");
+
+        assert_eq!(string.remove(0), '¡');
+        assert_eq!(string.remove(1), 'é');
+        assert_eq!(string, "hllo!");
+
+        let mut string = MyString::from("é");
+        assert_panics(|| string.insert(1, 'x'));
+        assert_panics(|| string.remove(1));
+        assert_panics(|| string.remove(2));
+        assert_panics(|| string.truncate(1));
+        assert_eq!(string, "é");
+    }
+
+    #[test]
+    fn test_chars() {
+        let string = MyString::from("aé🦀");
+        assert_eq!(string.chars().rev().collect::<String>(), "🦀éa");
+        assert_eq!(
+            string.char_indices().collect::<Vec<_>>(),
+            [(0, 'a'), (1, 'é'), (3, '🦀')]
+        );
+    }
+
+    #[test]
+    fn test_from_utf8() {
+        let valid = MyString::from_utf8(MyVec::from(*b"hello")).unwrap();
+        assert_eq!(valid, "hello");
+
+        // a 4 byte sequence cut off after 2 bytes
+        let error = MyString::from_utf8(MyVec::from([b'a', 240, 159])).unwrap_err();
+        assert_eq!(error.valid_up_to(), 1);
+        assert_eq!(error.error_len(), None);
+        assert_eq!(
+            error.to_string(),
+            "incomplete utf-8 byte sequence [f0, 9f] from index 1"
+        );
+
+        let error = MyString::from_utf8(MyVec::from([b'a', 0xc3, b'b'])).unwrap_err();
+        assert_eq!(error.error_len(), Some(1));
+        assert_eq!(
+            error.to_string(),
+            "invalid utf-8 sequence [c3] of 1 bytes from index 1"
+        );
+        assert_eq!(error.into_bytes(), [b'a', 0xc3, b'b']);
+    }
+
+    #[test]
+    fn test_from_utf8_unchecked() {
+        let sparkle_heart = MyVec::from([240, 159, 146, 150]);
+
+        // SAFETY: the bytes are valid UTF-8
+        let sparkle_heart = unsafe { MyString::from_utf8_unchecked(sparkle_heart) };
+
+        assert_eq!("💖", sparkle_heart);
+    }
+
+    #[test]
+    fn test_traits() {
+        let string: MyString = "hello".parse().unwrap();
+        assert_eq!(format!("{string} {string:?}"), "hello \"hello\"");
+        assert_eq!(string, String::from("hello"));
+        assert_eq!(String::from(string.clone()), "hello");
+        assert!(string.starts_with("he"));
+        let (a, b) = (MyString::from("a"), MyString::from("b"));
+        assert!(a < b);
+
+        let mut upper = string.clone();
+        upper.make_ascii_uppercase();
+        assert_eq!(upper, "HELLO");
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(string);
+        assert!(set.contains("hello"));
+
+        let mut built: MyString = ['a', 'b'].into_iter().collect();
+        std::fmt::Write::write_fmt(&mut built, format_args!("{}", 12)).unwrap();
+        assert_eq!(built, "ab12");
+    }
+}
@@ -0,0 +1,128 @@
+//! Element types for checking that containers drop everything exactly once, even when things panic,
+//! and a few helpers the tests share.
+
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::rc::Rc;
+
+use crate::MyVec;
+
+/// Hands out [`Tracked`] values and counts how many of them have been dropped.
+#[derive(Clone, Default)]
+pub struct DropCounter {
+    drops: Rc<Cell<usize>>,
+}
+
+impl DropCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn drops(&self) -> usize {
+        self.drops.get()
+    }
+
+    pub fn track(&self, value: i32) -> Tracked {
+        Tracked {
+            value,
+            drops: Rc::clone(&self.drops),
+            panic_on_drop: false,
+        }
+    }
+
+    /// Like [`DropCounter::track`], but the value panics when it's dropped (after being counted).
+    pub fn track_panicking(&self, value: i32) -> Tracked {
+        let mut tracked = self.track(value);
+        tracked.panic_on_drop = true;
+        tracked
+    }
+}
+
+#[derive(Debug)]
+pub struct Tracked {
+    pub value: i32,
+    drops: Rc<Cell<usize>>,
+    panic_on_drop: bool,
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value,
+            drops: Rc::clone(&self.drops),
+            panic_on_drop: false,
+        }
+    }
+}
+
+impl PartialEq for Tracked {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+        if self.panic_on_drop {
+            panic!("Tracked({}) panicked while dropping", self.value);
+        }
+    }
+}
+
+/// A `MyVec` of clones of `values`.
+pub fn from_slice<T: Clone>(values: &[T]) -> MyVec<T> {
+    let mut vec = MyVec::new();
+    for value in values {
+        vec.push(value.clone());
+    }
+    vec
+}
+
+/// A `MyVec` of `values`, each tracked by `counter`.
+pub fn tracked(counter: &DropCounter, values: &[i32]) -> MyVec<Tracked> {
+    let mut vec = MyVec::new();
+    for &value in values {
+        vec.push(counter.track(value));
+    }
+    vec
+}
+
+/// The values of some [`Tracked`]s, for comparing against a slice.
+pub fn values(vec: &MyVec<Tracked>) -> Vec<i32> {
+    vec.iter().map(|x| x.value).collect()
+}
+
+/// xorshift64*, plenty random enough for generating inputs, and the same every run.
+///
+/// Integration tests and benches are built against the library without `cfg(test)`, so they
+/// can't see this module and keep their own copy.
+pub struct Rng(u64);
+
+impl Rng {
+    /// # Panics
+    ///
+    /// Panics if `seed` is zero, which xorshift gets stuck on.
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(seed, 0, "xorshift gets stuck on a zero seed");
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Runs `f`, asserting that it panics.
+pub fn assert_panics<R>(f: impl FnOnce() -> R) {
+    let result = catch_unwind(AssertUnwindSafe(f));
+    assert!(result.is_err(), "expected a panic");
+}
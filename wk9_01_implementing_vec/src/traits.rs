@@ -0,0 +1,382 @@
+//! The standard library traits that make `MyVec` a drop-in for `Vec`.
+
+use core::borrow::{Borrow, BorrowMut};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::io;
+
+use alloc::vec::Vec;
+
+use crate::{Global, GrowthPolicy, MyAllocator, MyVec, RawBuf};
+
+impl<T: Clone, A: MyAllocator + Clone, G: GrowthPolicy> Clone for MyVec<T, A, G> {
+    fn clone(&self) -> Self {
+        // `extend_from_slice` bumps the length after every clone, so if one panics
+        // the clones made so far are dropped along with the half-built vector
+        let mut vec = Self::with_growth_in(self.allocator().clone());
+        vec.reserve_exact(self.len());
+        vec.extend_from_slice(self);
+        vec
+    }
+}
+
+impl<T: fmt::Debug, A: MyAllocator, G: GrowthPolicy> fmt::Debug for MyVec<T, A, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq<U>, U, A1: MyAllocator, G1: GrowthPolicy, A2: MyAllocator, G2: GrowthPolicy>
+    PartialEq<MyVec<U, A2, G2>> for MyVec<T, A1, G1>
+{
+    fn eq(&self, other: &MyVec<U, A2, G2>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: Eq, A: MyAllocator, G: GrowthPolicy> Eq for MyVec<T, A, G> {}
+
+// comparisons against everything else that's a bunch of `T`s in a row, both ways round
+macro_rules! impl_slice_eq {
+    ([$($generics:tt)*] $lhs:ty, $rhs:ty) => {
+        impl<T: PartialEq<U>, U, $($generics)*> PartialEq<$rhs> for $lhs {
+            fn eq(&self, other: &$rhs) -> bool {
+                self[..] == other[..]
+            }
+        }
+    };
+}
+
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy] MyVec<T, A, G>, Vec<U> }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy] MyVec<T, A, G>, [U] }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy] MyVec<T, A, G>, &[U] }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy] MyVec<T, A, G>, &mut [U] }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy, const N: usize] MyVec<T, A, G>, [U; N] }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy, const N: usize] MyVec<T, A, G>, &[U; N] }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy] Vec<T>, MyVec<U, A, G> }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy] [T], MyVec<U, A, G> }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy] &[T], MyVec<U, A, G> }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy] &mut [T], MyVec<U, A, G> }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy, const N: usize] [T; N], MyVec<U, A, G> }
+impl_slice_eq! { [A: MyAllocator, G: GrowthPolicy, const N: usize] &[T; N], MyVec<U, A, G> }
+
+impl<T: PartialOrd, A1: MyAllocator, G1: GrowthPolicy, A2: MyAllocator, G2: GrowthPolicy>
+    PartialOrd<MyVec<T, A2, G2>> for MyVec<T, A1, G1>
+{
+    fn partial_cmp(&self, other: &MyVec<T, A2, G2>) -> Option<Ordering> {
+        self[..].partial_cmp(&other[..])
+    }
+}
+
+impl<T: Ord, A: MyAllocator, G: GrowthPolicy> Ord for MyVec<T, A, G> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self[..].cmp(&other[..])
+    }
+}
+
+// hashes exactly like a slice does, which `Borrow<[T]>` relies on (e.g. looking up a
+// `HashSet<MyVec<T>>` with a `&[T]`)
+impl<T: Hash, A: MyAllocator, G: GrowthPolicy> Hash for MyVec<T, A, G> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&self[..], state);
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> Extend<T> for MyVec<T, A, G> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, A: MyAllocator, G: GrowthPolicy> Extend<&'a T> for MyVec<T, A, G> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T> FromIterator<T> for MyVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = Self::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T> From<Vec<T>> for MyVec<T> {
+    /// Takes over the `Vec`'s buffer without copying.
+    fn from(vec: Vec<T>) -> Self {
+        let mut vec = ManuallyDrop::new(vec);
+
+        // `Vec` gets its memory from the global allocator with the same layout we use (`capacity`
+        // elements of `T`), and uses a dangling pointer when it hasn't allocated, just like us
+        // SAFETY: `Vec`'s pointer is never null
+        let buf = unsafe {
+            RawBuf::from_raw_parts_in(
+                NonNull::new_unchecked(vec.as_mut_ptr()),
+                vec.capacity(),
+                Global,
+            )
+        };
+        Self {
+            buf,
+            size: vec.len(),
+            _marker: PhantomData,
+            _growth: PhantomData,
+        }
+    }
+}
+
+impl<T> From<MyVec<T>> for Vec<T> {
+    /// Hands the buffer over to a `Vec` without copying.
+    fn from(vec: MyVec<T>) -> Self {
+        let vec = ManuallyDrop::new(vec);
+
+        // SAFETY: same as `From<Vec<T>>`, the layouts and allocator match what `Vec` expects.
+        //         For zero-sized types `Vec` ignores the capacity we pass
+        unsafe { Vec::from_raw_parts(vec.buf.ptr(), vec.size, vec.capacity()) }
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for MyVec<T> {
+    fn from(array: [T; N]) -> Self {
+        let array = ManuallyDrop::new(array);
+        let mut vec = Self::with_capacity(N);
+
+        // SAFETY: we reserved room for `N` elements, and the array won't drop the ones we moved out
+        unsafe {
+            core::ptr::copy_nonoverlapping(array.as_ptr(), vec.buf.ptr(), N);
+            vec.size = N;
+        }
+        vec
+    }
+}
+
+impl<T: Clone> From<&[T]> for MyVec<T> {
+    fn from(slice: &[T]) -> Self {
+        let mut vec = Self::with_capacity(slice.len());
+        vec.extend_from_slice(slice);
+        vec
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> AsRef<[T]> for MyVec<T, A, G> {
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> AsMut<[T]> for MyVec<T, A, G> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> AsRef<MyVec<T, A, G>> for MyVec<T, A, G> {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> AsMut<MyVec<T, A, G>> for MyVec<T, A, G> {
+    fn as_mut(&mut self) -> &mut Self {
+        self
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> Borrow<[T]> for MyVec<T, A, G> {
+    fn borrow(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, A: MyAllocator, G: GrowthPolicy> BorrowMut<[T]> for MyVec<T, A, G> {
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: MyAllocator, G: GrowthPolicy> io::Write for MyVec<u8, A, G> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_panics, DropCounter};
+    use crate::CountingAllocator;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    #[cfg(feature = "std")]
+    use std::io::Write;
+
+    fn hash_of<H: Hash + ?Sized>(value: &H) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_clone_debug_default() {
+        let vec: MyVec<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        let mut copy = vec.clone();
+        copy.push("c".to_string());
+        assert_eq!(format!("{vec:?}"), r#"["a", "b"]"#);
+        assert_eq!(format!("{copy:?}"), r#"["a", "b", "c"]"#);
+        assert_eq!(format!("{:?}", MyVec::<i32>::default()), "[]");
+
+        let counter = CountingAllocator::new();
+        let mut vec = MyVec::new_in(&counter);
+        vec.push(1);
+        let copy = vec.clone();
+        assert_eq!(copy, vec);
+        assert_eq!(counter.live_allocations(), 2);
+    }
+
+    #[test]
+    fn test_clone_panicking() {
+        #[derive(Debug)]
+        struct PanicOnClone(crate::test_utils::Tracked);
+
+        impl Clone for PanicOnClone {
+            fn clone(&self) -> Self {
+                if self.0.value == 2 {
+                    panic!("clone panicked");
+                }
+                Self(self.0.clone())
+            }
+        }
+
+        let counter = DropCounter::new();
+        let vec: MyVec<_> = (0..4).map(|i| PanicOnClone(counter.track(i))).collect();
+
+        assert_panics(|| vec.clone());
+        // the two clones that were made got dropped, and the original is untouched
+        assert_eq!(counter.drops(), 2);
+        assert_eq!(vec.len(), 4);
+        drop(vec);
+        assert_eq!(counter.drops(), 6);
+    }
+
+    #[test]
+    fn test_equality() {
+        let vec = MyVec::from([1, 2, 3]);
+        assert_eq!(vec, MyVec::from(vec![1, 2, 3]));
+        assert_eq!(vec, vec![1, 2, 3]);
+        assert_eq!(vec![1, 2, 3], vec);
+        assert_eq!(vec, [1, 2, 3]);
+        assert_eq!([1, 2, 3], vec);
+        assert_eq!(vec, &[1, 2, 3]);
+        assert_eq!(vec, [1, 2, 3][..]);
+        assert_eq!(&[1, 2, 3][..], vec);
+        assert_ne!(vec, [1, 2]);
+
+        // elements only need to be comparable, not the same type
+        let strings = MyVec::from(["a".to_string()]);
+        assert_eq!(strings, ["a"]);
+
+        let counter = CountingAllocator::new();
+        let mut other = MyVec::new_in(&counter);
+        other.extend([1, 2, 3]);
+        assert_eq!(vec, other);
+    }
+
+    #[test]
+    fn test_ordering_and_hash() {
+        let a = MyVec::from([1, 2, 3]);
+        let b = MyVec::from([1, 3]);
+        assert!(a < b);
+        assert_eq!(a.cmp(&a.clone()), Ordering::Equal);
+        assert_eq!(MyVec::<i32>::new().partial_cmp(&a), Some(Ordering::Less));
+
+        assert_eq!(hash_of(&a), hash_of(&[1, 2, 3][..]));
+        assert_eq!(hash_of(&a), hash_of(&vec![1, 2, 3]));
+
+        let mut set = HashSet::new();
+        set.insert(a);
+        assert!(set.contains(&[1, 2, 3][..]));
+    }
+
+    #[test]
+    fn test_extend_and_collect() {
+        let mut vec: MyVec<i32> = (0..5).collect();
+        vec.extend(5..8);
+        vec.extend(&[8, 9]);
+        assert_eq!(vec, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_conversions() {
+        let std_vec = vec!["x".to_string(), "y".to_string()];
+        let mut vec = MyVec::from(std_vec);
+        vec.push("z".to_string());
+        let back: Vec<String> = vec.into();
+        assert_eq!(back, ["x", "y", "z"]);
+
+        let empty: Vec<u64> = MyVec::new().into();
+        assert!(empty.is_empty());
+        let mut empty = MyVec::from(Vec::<u64>::new());
+        empty.push(1);
+        assert_eq!(empty, [1]);
+
+        let units: Vec<()> = MyVec::from(vec![(); 5]).into();
+        assert_eq!(units.len(), 5);
+
+        let counter = DropCounter::new();
+        let vec = MyVec::from([counter.track(1), counter.track(2)]);
+        assert_eq!(counter.drops(), 0);
+        drop(vec);
+        assert_eq!(counter.drops(), 2);
+
+        let vec = MyVec::from(&["a", "b"][..]);
+        assert_eq!(vec, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_as_ref_borrow() {
+        fn sum(values: impl AsRef<[i32]>) -> i32 {
+            values.as_ref().iter().sum()
+        }
+        fn zero_first(mut values: impl AsMut<[i32]>) {
+            values.as_mut()[0] = 0;
+        }
+
+        let mut vec = MyVec::from([1, 2, 3]);
+        assert_eq!(sum(&vec), 6);
+        zero_first(&mut vec);
+        let borrowed: &[i32] = vec.borrow();
+        assert_eq!(borrowed, [0, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_io_write() {
+        let mut buf = MyVec::new();
+        let course = "comp6991";
+        write!(buf, "{course}-{}", 2024).unwrap();
+        buf.write_all(b"!").unwrap();
+        buf.flush().unwrap();
+        assert_eq!(buf, b"comp6991-2024!");
+    }
+}
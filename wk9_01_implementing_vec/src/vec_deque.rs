@@ -0,0 +1,711 @@
+//! A double-ended queue implemented as a ring buffer.
+
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::{Index, IndexMut};
+
+use crate::{Doubling, Iter as SliceIter, IterMut as SliceIterMut, RawBuf};
+
+/// A double-ended queue, like `VecDeque<T>`, stored in a growable ring buffer.
+///
+/// The elements live in `len` consecutive slots starting at `head`, wrapping around to the
+/// start of the buffer when they run off the end. So pushing and popping at either end is
+/// O(1), but the elements aren't always one contiguous slice (see [`as_slices`]).
+///
+/// [`as_slices`]: MyVecDeque::as_slices
+pub struct MyVecDeque<T> {
+    // knows nothing about which slots are initialised, that's what `head` and `len` are for
+    buf: RawBuf<T>,
+    head: usize,
+    len: usize,
+}
+
+impl<T> MyVecDeque<T> {
+    pub fn new() -> Self {
+        Self {
+            buf: RawBuf::new(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: RawBuf::with_capacity(capacity),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        if self.len == self.capacity() {
+            self.grow();
+        }
+
+        // SAFETY: there's a free slot just past the back
+        unsafe { self.slot(self.len).write(value) };
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        if self.len == self.capacity() {
+            self.grow();
+        }
+
+        self.head = self.wrap_sub(self.head, 1);
+        // SAFETY: there was a free slot just before the front, which is now the new head
+        unsafe { self.slot(0).write(value) };
+        self.len += 1;
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+        // SAFETY: the old back was initialised, and is now outside the deque
+        Some(unsafe { self.slot(self.len).read() })
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // SAFETY: the front is initialised, and is outside the deque once `head` moves on
+        let value = unsafe { self.slot(0).read() };
+        self.head = self.physical_index(1);
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.wrapping_sub(1))
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        // SAFETY: `index < len`, so the slot is initialised
+        Some(unsafe { &*self.slot(index) })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        // SAFETY: same as `get`
+        Some(unsafe { &mut *self.slot(index) })
+    }
+
+    pub fn clear(&mut self) {
+        let (front, back) = self.as_mut_slices();
+        let (front, back): (*mut [T], *mut [T]) = (front, back);
+        // forget the elements first, so if a destructor panics we don't drop them again
+        self.head = 0;
+        self.len = 0;
+        // SAFETY: both halves were initialised and are now outside the deque. If dropping `front`
+        //         panics, `back` is leaked rather than double-dropped
+        unsafe {
+            front.drop_in_place();
+            back.drop_in_place();
+        }
+    }
+
+    /// The elements in order, as the part from `head` to the end of the buffer followed by the
+    /// part that wrapped around to the start (empty if nothing wrapped).
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (front_len, back_len) = self.slice_lens();
+        // SAFETY: `head..head + front_len` and `0..back_len` are the initialised slots
+        unsafe {
+            let ptr = self.buf.ptr();
+            (
+                core::slice::from_raw_parts(ptr.add(self.head), front_len),
+                core::slice::from_raw_parts(ptr, back_len),
+            )
+        }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (front_len, back_len) = self.slice_lens();
+        // SAFETY: same as `as_slices`, and the two halves don't overlap
+        unsafe {
+            let ptr = self.buf.ptr();
+            (
+                core::slice::from_raw_parts_mut(ptr.add(self.head), front_len),
+                core::slice::from_raw_parts_mut(ptr, back_len),
+            )
+        }
+    }
+
+    /// Moves the elements around so they're one contiguous slice, and returns it.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        let (front_len, back_len) = self.slice_lens();
+
+        if back_len != 0 {
+            let cap = self.capacity();
+            let free = cap - self.len;
+            let ptr = self.buf.ptr();
+
+            // SAFETY: every copy stays inside the buffer, and only ever overwrites free slots or
+            //         elements that have already been copied elsewhere
+            unsafe {
+                if free >= front_len {
+                    // room to slide the back half right and put the front half before it
+                    //   [B B . . F] -> [. . B B F] -> [F . B B .]
+                    core::ptr::copy(ptr, ptr.add(front_len), back_len);
+                    core::ptr::copy_nonoverlapping(ptr.add(self.head), ptr, front_len);
+                    self.head = 0;
+                } else if free >= back_len {
+                    // room to slide the front half left and put the back half after it
+                    //   [B . F F F] -> [B F F F .] -> [. F F F B]
+                    let new_head = free;
+                    core::ptr::copy(ptr.add(self.head), ptr.add(new_head), front_len);
+                    core::ptr::copy_nonoverlapping(ptr, ptr.add(new_head + front_len), back_len);
+                    self.head = new_head;
+                } else {
+                    // not much room either way: close the gap so both halves are in
+                    // `0..len` (back first), then rotate them into order
+                    core::ptr::copy(ptr.add(self.head), ptr.add(back_len), front_len);
+                    core::slice::from_raw_parts_mut(ptr, self.len).rotate_left(back_len);
+                    self.head = 0;
+                }
+            }
+        }
+
+        self.as_mut_slices().0
+    }
+
+    /// Rotates the deque `n` places to the left, so the element at index `n` ends up at the front.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n > len`.
+    pub fn rotate_left(&mut self, n: usize) {
+        assert!(
+            n <= self.len,
+            "rotation amount (is {n}) should be <= len (is {})",
+            self.len
+        );
+
+        if n <= self.len / 2 {
+            for _ in 0..n {
+                // the deque never grows here: we've just freed up the slot we push into
+                let value = self.pop_front().unwrap();
+                self.push_back(value);
+            }
+        } else {
+            self.rotate_right(self.len - n);
+        }
+    }
+
+    /// Rotates the deque `n` places to the right, so the first element ends up at index `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n > len`.
+    pub fn rotate_right(&mut self, n: usize) {
+        assert!(
+            n <= self.len,
+            "rotation amount (is {n}) should be <= len (is {})",
+            self.len
+        );
+
+        if n <= self.len / 2 {
+            for _ in 0..n {
+                let value = self.pop_back().unwrap();
+                self.push_front(value);
+            }
+        } else {
+            self.rotate_left(self.len - n);
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (front, back) = self.as_slices();
+        Iter {
+            front: SliceIter::new(front),
+            back: SliceIter::new(back),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (front, back) = self.as_mut_slices();
+        IterMut {
+            front: SliceIterMut::new(front),
+            back: SliceIterMut::new(back),
+        }
+    }
+
+    /// Grows the buffer, then moves whichever part of a wrapped-around deque is smaller so the
+    /// elements are consecutive (modulo the new capacity) again.
+    fn grow(&mut self) {
+        let old_capacity = self.capacity();
+        self.buf.grow_one::<Doubling>(self.len);
+        let new_capacity = self.capacity();
+
+        let (front_len, back_len) = self.slice_lens_for(old_capacity);
+        if back_len == 0 {
+            return;
+        }
+
+        let ptr = self.buf.ptr();
+        // SAFETY: the buffer has at least doubled, so there's room for either half past
+        //         `old_capacity` without overlapping anything initialised
+        unsafe {
+            if back_len < front_len && back_len <= new_capacity - old_capacity {
+                //   [B . F F F . . . . .] -> [. . F F F B . . . .]
+                core::ptr::copy_nonoverlapping(ptr, ptr.add(old_capacity), back_len);
+            } else {
+                //   [B B B . F . . . . .] -> [B B B . . . . . . F]
+                let new_head = new_capacity - front_len;
+                core::ptr::copy(ptr.add(self.head), ptr.add(new_head), front_len);
+                self.head = new_head;
+            }
+        }
+    }
+
+    /// The lengths of the two halves returned by `as_slices`.
+    fn slice_lens(&self) -> (usize, usize) {
+        self.slice_lens_for(self.capacity())
+    }
+
+    fn slice_lens_for(&self, capacity: usize) -> (usize, usize) {
+        let until_end = capacity - self.head;
+        if self.len <= until_end {
+            (self.len, 0)
+        } else {
+            (until_end, self.len - until_end)
+        }
+    }
+
+    /// Where the element at logical `index` lives in the buffer.
+    fn physical_index(&self, index: usize) -> usize {
+        // written so it can't overflow, even with the `usize::MAX` capacity of zero-sized types
+        let until_end = self.capacity() - self.head;
+        if index >= until_end {
+            index - until_end
+        } else {
+            self.head + index
+        }
+    }
+
+    fn wrap_sub(&self, index: usize, n: usize) -> usize {
+        if index >= n {
+            index - n
+        } else {
+            self.capacity() - (n - index)
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The caller must ensure that `index` is less than the capacity.
+    unsafe fn slot(&self, index: usize) -> *mut T {
+        unsafe { self.buf.ptr().add(self.physical_index(index)) }
+    }
+}
+
+impl<T> Drop for MyVecDeque<T> {
+    fn drop(&mut self) {
+        // `buf` frees the allocation, but the elements are up to us
+        self.clear();
+    }
+}
+
+impl<T> Default for MyVecDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<usize> for MyVecDeque<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        let len = self.len;
+        self.get(index).unwrap_or_else(|| {
+            panic!("index out of bounds: the len is {len} but the index is {index}")
+        })
+    }
+}
+
+impl<T> IndexMut<usize> for MyVecDeque<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len;
+        self.get_mut(index).unwrap_or_else(|| {
+            panic!("index out of bounds: the len is {len} but the index is {index}")
+        })
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MyVecDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone> Clone for MyVecDeque<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for MyVecDeque<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for MyVecDeque<T> {}
+
+impl<T> Extend<T> for MyVecDeque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for MyVecDeque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = Self::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+/// Iterator over a [`MyVecDeque`]'s elements, front to back (or back to front with `.rev()`).
+#[derive(Clone)]
+pub struct Iter<'a, T> {
+    front: SliceIter<'a, T>,
+    back: SliceIter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.front.next().or_else(|| self.back.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.next_back().or_else(|| self.front.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// Mutable iterator over a [`MyVecDeque`]'s elements.
+pub struct IterMut<'a, T> {
+    front: SliceIterMut<'a, T>,
+    back: SliceIterMut<'a, T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.front.next().or_else(|| self.back.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.next_back().or_else(|| self.front.next_back())
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+/// Owning iterator over a [`MyVecDeque`], which just pops from whichever end is asked for.
+pub struct IntoIter<T> {
+    deque: MyVecDeque<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.deque.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.deque.len, Some(self.deque.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.deque.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for MyVecDeque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { deque: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a MyVecDeque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut MyVecDeque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::test_utils::{assert_panics, DropCounter};
+
+    /// A deque with capacity 8 whose elements `values` start at buffer slot `head`.
+    fn wrapped(head: usize, values: &[i32]) -> MyVecDeque<i32> {
+        let mut deque = MyVecDeque::with_capacity(8);
+        for _ in 0..head {
+            deque.push_back(0);
+            deque.pop_front();
+        }
+        for &value in values {
+            deque.push_back(value);
+        }
+        assert_eq!(deque.capacity(), 8);
+        deque
+    }
+
+    fn contents(deque: &MyVecDeque<i32>) -> Vec<i32> {
+        deque.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_push_pop_both_ends() {
+        let mut deque = MyVecDeque::new();
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+
+        deque.push_back(2);
+        deque.push_back(3);
+        deque.push_front(1);
+        deque.push_front(0);
+        assert_eq!(contents(&deque), [0, 1, 2, 3]);
+        assert_eq!((deque.front(), deque.back()), (Some(&0), Some(&3)));
+
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(3));
+        deque[1] = 20;
+        assert_eq!(deque[0], 1);
+        assert_eq!(deque.get(1), Some(&20));
+        assert_eq!(deque.get(2), None);
+        assert_panics(|| deque[2]);
+    }
+
+    #[test]
+    fn test_as_slices_wraps() {
+        let deque = wrapped(6, &[1, 2, 3, 4]);
+        assert_eq!(deque.as_slices(), (&[1, 2][..], &[3, 4][..]));
+
+        let deque = wrapped(2, &[1, 2, 3, 4]);
+        assert_eq!(deque.as_slices(), (&[1, 2, 3, 4][..], &[][..]));
+    }
+
+    #[test]
+    fn test_grow_across_wrap() {
+        // short back half gets moved past the old end, long back half means the front moves instead
+        for head in 1..8 {
+            let values: Vec<i32> = (1..=8).collect();
+            let mut deque = wrapped(head, &values);
+            deque.push_back(9);
+            assert!(deque.capacity() > 8);
+            assert_eq!(
+                contents(&deque),
+                (1..=9).collect::<Vec<_>>(),
+                "head = {head}"
+            );
+
+            let mut deque = wrapped(head, &values);
+            deque.push_front(0);
+            assert_eq!(
+                contents(&deque),
+                (0..=8).collect::<Vec<_>>(),
+                "head = {head}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_make_contiguous() {
+        // covers all three cases: room for the front half, room for the back half, and neither
+        for (head, len) in [(6, 4), (7, 3), (3, 6), (5, 7), (4, 8), (7, 8)] {
+            let values: Vec<i32> = (0..len).collect();
+            let mut deque = wrapped(head as usize, &values);
+            assert_eq!(
+                deque.make_contiguous(),
+                &values[..],
+                "head = {head}, len = {len}"
+            );
+            assert_eq!(deque.as_slices().1, &[], "head = {head}, len = {len}");
+            deque.push_back(len);
+            assert_eq!(deque.pop_front(), Some(0));
+        }
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut deque: MyVecDeque<i32> = (0..5).collect();
+        deque.rotate_left(2);
+        assert_eq!(contents(&deque), [2, 3, 4, 0, 1]);
+        deque.rotate_left(4);
+        assert_eq!(contents(&deque), [1, 2, 3, 4, 0]);
+        deque.rotate_right(3);
+        assert_eq!(contents(&deque), [3, 4, 0, 1, 2]);
+        deque.rotate_right(5);
+        assert_eq!(contents(&deque), [3, 4, 0, 1, 2]);
+        assert_panics(|| deque.clone().rotate_left(6));
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut deque = wrapped(5, &[1, 2, 3, 4, 5]);
+        assert_eq!(
+            deque.iter().rev().copied().collect::<Vec<_>>(),
+            [5, 4, 3, 2, 1]
+        );
+        assert_eq!(deque.iter().len(), 5);
+
+        for x in &mut deque {
+            *x *= 10;
+        }
+        let mut iter = deque.iter();
+        assert_eq!(iter.next(), Some(&10));
+        assert_eq!(iter.next_back(), Some(&50));
+        assert_eq!(iter.collect::<Vec<_>>(), [&20, &30, &40]);
+
+        let mut into_iter = deque.into_iter();
+        assert_eq!(into_iter.next_back(), Some(50));
+        assert_eq!(into_iter.collect::<Vec<_>>(), [10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn test_drops() {
+        let counter = DropCounter::new();
+        let mut deque = MyVecDeque::new();
+        for i in 0..10 {
+            if i % 2 == 0 {
+                deque.push_back(counter.track(i));
+            } else {
+                deque.push_front(counter.track(i));
+            }
+        }
+        drop(deque.pop_front());
+        assert_eq!(counter.drops(), 1);
+
+        let mut iter = deque.into_iter();
+        iter.next();
+        assert_eq!(counter.drops(), 2);
+        drop(iter);
+        assert_eq!(counter.drops(), 10);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let mut deque = MyVecDeque::new();
+        for _ in 0..5 {
+            deque.push_front(());
+            deque.push_back(());
+        }
+        assert_eq!(deque.len(), 10);
+        deque.rotate_left(3);
+        assert_eq!(deque.make_contiguous().len(), 10);
+        assert_eq!(deque.iter().rev().count(), 10);
+        assert_eq!(deque.pop_front(), Some(()));
+        assert_eq!(deque.into_iter().count(), 9);
+    }
+
+    #[test]
+    fn test_against_std() {
+        // a cheap xorshift, so the sequence of operations is the same every run
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut ours = MyVecDeque::new();
+        let mut std = VecDeque::new();
+        for i in 0..2000 {
+            match next() % 6 {
+                0 | 1 => {
+                    ours.push_back(i);
+                    std.push_back(i);
+                }
+                2 => {
+                    ours.push_front(i);
+                    std.push_front(i);
+                }
+                3 => assert_eq!(ours.pop_front(), std.pop_front()),
+                4 => assert_eq!(ours.pop_back(), std.pop_back()),
+                _ if !std.is_empty() => {
+                    let n = next() as usize % std.len();
+                    ours.rotate_left(n);
+                    std.rotate_left(n);
+                }
+                _ => {}
+            }
+            assert!(ours.iter().eq(std.iter()));
+        }
+        assert_eq!(ours.make_contiguous(), std.make_contiguous());
+    }
+}
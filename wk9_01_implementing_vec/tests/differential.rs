@@ -0,0 +1,443 @@
+// Differential testing: run the same random sequence of operations against `MyVec` and `Vec`,
+// and check after every step that they agree. When a sequence fails, it gets shrunk down to
+// a minimal reproduction before being reported.
+//
+// No external crates: the random number generator and the shrinker are both in here.
+//
+// To pick a different seed:   DIFF_SEED=1234 cargo test --test differential
+// To run under Miri:          cargo +nightly miri test --test differential
+// (Miri is slow, so it runs far fewer and shorter sequences.)
+
+use std::cell::Cell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use wk9_01_implementing_vec::MyVec;
+
+#[cfg(not(miri))]
+const CASES: usize = 300;
+#[cfg(not(miri))]
+const OPS_PER_CASE: usize = 200;
+
+#[cfg(miri)]
+const CASES: usize = 8;
+#[cfg(miri)]
+const OPS_PER_CASE: usize = 40;
+
+/// xorshift64*, which is plenty random enough for picking operations.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn value(&mut self) -> i32 {
+        self.below(1000) as i32
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Push(i32),
+    Pop,
+    // indices are taken modulo the current length (or just past it), so every sequence is valid
+    Get(usize),
+    Insert(usize, i32),
+    Remove(usize),
+    SwapRemove(usize),
+    Reserve(usize),
+    ReserveExact(usize),
+    ShrinkToFit,
+    Truncate(usize),
+    Clear,
+    Iterate,
+    IterateBack,
+    AddToAll(i32),
+    Clone,
+    // consume the vector with `into_iter`, take some from the front, then collect the rest back up
+    IntoIterSkip(usize),
+}
+
+impl Op {
+    fn random(rng: &mut Rng) -> Self {
+        match rng.below(20) {
+            // pushes are the most common, so the vectors actually get big
+            0..=4 => Op::Push(rng.value()),
+            5 | 6 => Op::Pop,
+            7 => Op::Get(rng.below(64)),
+            8 | 9 => Op::Insert(rng.below(64), rng.value()),
+            10 => Op::Remove(rng.below(64)),
+            11 => Op::SwapRemove(rng.below(64)),
+            12 => Op::Reserve(rng.below(64)),
+            13 => Op::ReserveExact(rng.below(64)),
+            14 => Op::ShrinkToFit,
+            15 => Op::Truncate(rng.below(64)),
+            16 => match rng.below(4) {
+                0 => Op::Clear,
+                _ => Op::Clone,
+            },
+            17 => Op::Iterate,
+            18 => Op::IterateBack,
+            _ => match rng.below(2) {
+                0 => Op::AddToAll(rng.value()),
+                _ => Op::IntoIterSkip(rng.below(8)),
+            },
+        }
+    }
+
+    /// Simpler versions of this op, for the shrinker to try.
+    fn simplifications(self) -> Vec<Op> {
+        let mut simpler = match self {
+            Op::Push(v) if v != 0 => vec![Op::Push(0)],
+            Op::Insert(i, v) => vec![Op::Push(v), Op::Insert(0, v), Op::Insert(i, 0)],
+            Op::Get(i) if i != 0 => vec![Op::Get(0)],
+            Op::Remove(i) if i != 0 => vec![Op::Remove(0)],
+            Op::SwapRemove(i) if i != 0 => vec![Op::SwapRemove(0), Op::Remove(i)],
+            Op::Reserve(n) if n != 0 => vec![Op::Reserve(0)],
+            Op::ReserveExact(n) if n != 0 => vec![Op::ReserveExact(0)],
+            Op::Truncate(n) if n != 0 => vec![Op::Truncate(0), Op::Clear],
+            Op::AddToAll(v) if v != 0 => vec![Op::AddToAll(0)],
+            Op::IntoIterSkip(n) if n != 0 => vec![Op::IntoIterSkip(0)],
+            _ => vec![],
+        };
+        // e.g. `Insert(0, v)` would otherwise "simplify" to itself forever
+        simpler.retain(|&op| op != self);
+        simpler
+    }
+}
+
+/// How many `Counted` values have been created, cloned and dropped.
+#[derive(Default)]
+struct Counters {
+    created: Cell<usize>,
+    clones: Cell<usize>,
+    drops: Cell<usize>,
+}
+
+impl Counters {
+    fn make(&self, value: i32) -> Counted<'_> {
+        self.created.set(self.created.get() + 1);
+        Counted {
+            value,
+            counters: self,
+        }
+    }
+
+    fn live(&self) -> usize {
+        self.created.get() + self.clones.get() - self.drops.get()
+    }
+}
+
+struct Counted<'c> {
+    value: i32,
+    counters: &'c Counters,
+}
+
+impl Clone for Counted<'_> {
+    fn clone(&self) -> Self {
+        self.counters.clones.set(self.counters.clones.get() + 1);
+        Self {
+            value: self.value,
+            counters: self.counters,
+        }
+    }
+}
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        self.counters.drops.set(self.counters.drops.get() + 1);
+    }
+}
+
+impl PartialEq for Counted<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl fmt::Debug for Counted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// Runs `ops` against both vectors, returning a description of the first difference.
+fn run(ops: &[Op]) -> Result<(), String> {
+    let mine_counters = Counters::default();
+    let std_counters = Counters::default();
+
+    {
+        let mut mine = MyVec::new();
+        let mut theirs = Vec::new();
+
+        for (step, &op) in ops.iter().enumerate() {
+            let fail = |what: String| Err(format!("step {step} ({op:?}): {what}"));
+
+            let len = theirs.len();
+            match op {
+                Op::Push(v) => {
+                    mine.push(mine_counters.make(v));
+                    theirs.push(std_counters.make(v));
+                }
+                Op::Pop => {
+                    let (a, b) = (mine.pop(), theirs.pop());
+                    if a != b {
+                        return fail(format!("pop returned {a:?}, expected {b:?}"));
+                    }
+                }
+                Op::Get(i) => {
+                    // go a little past the end too, to check out of bounds gives `None`
+                    let i = i % (len + 2);
+                    let (a, b) = (mine.get(i), theirs.get(i));
+                    if a != b {
+                        return fail(format!("get({i}) returned {a:?}, expected {b:?}"));
+                    }
+                }
+                Op::Insert(i, v) => {
+                    let i = i % (len + 1);
+                    mine.insert(i, mine_counters.make(v));
+                    theirs.insert(i, std_counters.make(v));
+                }
+                Op::Remove(i) | Op::SwapRemove(i) if len > 0 => {
+                    let i = i % len;
+                    let (a, b) = if matches!(op, Op::Remove(_)) {
+                        (mine.remove(i), theirs.remove(i))
+                    } else {
+                        (mine.swap_remove(i), theirs.swap_remove(i))
+                    };
+                    if a != b {
+                        return fail(format!("removed {a:?}, expected {b:?}"));
+                    }
+                }
+                Op::Remove(_) | Op::SwapRemove(_) => {}
+                Op::Reserve(n) => {
+                    mine.reserve(n);
+                    theirs.reserve(n);
+                    if mine.capacity() < len + n {
+                        return fail(format!("capacity {} after reserve", mine.capacity()));
+                    }
+                }
+                Op::ReserveExact(n) => {
+                    mine.reserve_exact(n);
+                    theirs.reserve_exact(n);
+                    if mine.capacity() < len + n {
+                        return fail(format!("capacity {} after reserve_exact", mine.capacity()));
+                    }
+                }
+                Op::ShrinkToFit => {
+                    mine.shrink_to_fit();
+                    theirs.shrink_to_fit();
+                    if mine.capacity() != len {
+                        return fail(format!("capacity {} after shrink_to_fit", mine.capacity()));
+                    }
+                }
+                Op::Truncate(n) => {
+                    mine.truncate(n);
+                    theirs.truncate(n);
+                }
+                Op::Clear => {
+                    mine.clear();
+                    theirs.clear();
+                }
+                Op::Iterate => {
+                    if !mine.iter().eq(theirs.iter()) {
+                        return fail("iter yielded different elements".to_string());
+                    }
+                }
+                Op::IterateBack => {
+                    if !mine.iter().rev().eq(theirs.iter().rev()) {
+                        return fail("iter().rev() yielded different elements".to_string());
+                    }
+                }
+                Op::AddToAll(v) => {
+                    for x in &mut mine {
+                        x.value += v;
+                    }
+                    for x in &mut theirs {
+                        x.value += v;
+                    }
+                }
+                Op::Clone => {
+                    let (a, b) = (mine.clone(), theirs.clone());
+                    if a != b {
+                        return fail(format!("clone gave {a:?}, expected {b:?}"));
+                    }
+                    if mine_counters.clones.get() != std_counters.clones.get() {
+                        return fail("clone cloned a different number of elements".to_string());
+                    }
+                }
+                Op::IntoIterSkip(n) => {
+                    let mut a = std::mem::take(&mut mine).into_iter();
+                    let mut b = std::mem::take(&mut theirs).into_iter();
+                    if a.len() != b.len() {
+                        return fail(format!(
+                            "into_iter has length {}, expected {}",
+                            a.len(),
+                            b.len()
+                        ));
+                    }
+                    for _ in 0..n {
+                        let (x, y) = (a.next(), b.next());
+                        if x != y {
+                            return fail(format!("into_iter yielded {x:?}, expected {y:?}"));
+                        }
+                    }
+                    mine = a.collect();
+                    theirs = b.collect();
+                }
+            }
+
+            if mine != theirs {
+                return fail(format!("contents are {mine:?}, expected {theirs:?}"));
+            }
+            if mine.len() != theirs.len() || mine.capacity() < mine.len() {
+                return fail(format!(
+                    "len {} and capacity {}, expected len {}",
+                    mine.len(),
+                    mine.capacity(),
+                    theirs.len()
+                ));
+            }
+            // everything that's been made and not dropped is in the vector
+            if mine_counters.live() != mine.len() {
+                return fail(format!(
+                    "{} live elements but the vector holds {}",
+                    mine_counters.live(),
+                    mine.len()
+                ));
+            }
+        }
+    }
+
+    if mine_counters.live() != 0 {
+        return Err(format!("{} elements leaked", mine_counters.live()));
+    }
+    if mine_counters.drops.get() != std_counters.drops.get() {
+        return Err(format!(
+            "dropped {} elements, expected {}",
+            mine_counters.drops.get(),
+            std_counters.drops.get()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Runs `ops`, turning a panic into a failure too.
+fn check(ops: &[Op]) -> Result<(), String> {
+    match panic::catch_unwind(AssertUnwindSafe(|| run(ops))) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| payload.downcast_ref::<&str>().copied())
+                .unwrap_or("<non-string panic>");
+            Err(format!("panicked: {message}"))
+        }
+    }
+}
+
+/// Shrinks a failing sequence: first by throwing away chunks of ops (halves, then quarters,
+/// down to single ops), then by simplifying the ops that are left, until nothing more helps.
+fn shrink(mut ops: Vec<Op>, fails: impl Fn(&[Op]) -> bool) -> Vec<Op> {
+    loop {
+        let mut progress = false;
+
+        let mut chunk = ops.len().div_ceil(2).max(1);
+        while chunk >= 1 {
+            let mut start = 0;
+            while start < ops.len() {
+                let mut candidate = ops.clone();
+                candidate.drain(start..(start + chunk).min(ops.len()));
+                if fails(&candidate) {
+                    ops = candidate;
+                    progress = true;
+                } else {
+                    start += chunk;
+                }
+            }
+            chunk /= 2;
+        }
+
+        for i in 0..ops.len() {
+            for simpler in ops[i].simplifications() {
+                let mut candidate = ops.clone();
+                candidate[i] = simpler;
+                if fails(&candidate) {
+                    ops = candidate;
+                    progress = true;
+                    break;
+                }
+            }
+        }
+
+        if !progress {
+            return ops;
+        }
+    }
+}
+
+fn base_seed() -> u64 {
+    std::env::var("DIFF_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(6991)
+}
+
+#[test]
+fn test_matches_std_vec() {
+    let base = base_seed();
+
+    for case in 0..CASES {
+        let seed = base.wrapping_add(case as u64);
+        let mut rng = Rng::new(seed);
+        let ops: Vec<Op> = (0..OPS_PER_CASE).map(|_| Op::random(&mut rng)).collect();
+
+        if let Err(first_error) = check(&ops) {
+            // the shrinker reruns lots of failing sequences, so don't print every panic
+            let hook = panic::take_hook();
+            panic::set_hook(Box::new(|_| {}));
+            let minimal = shrink(ops, |ops| check(ops).is_err());
+            panic::set_hook(hook);
+
+            let error = check(&minimal).unwrap_err();
+            panic!(
+                "seed {seed} failed: {first_error}\n\
+                 minimal reproduction ({} ops): {minimal:?}\n\
+                 which fails with: {error}",
+                minimal.len()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_shrinker_finds_minimal_case() {
+    // pretend the bug is "three pushes of anything, then a pop"
+    let fails = |ops: &[Op]| {
+        let pushes = ops.iter().filter(|op| matches!(op, Op::Push(_))).count();
+        pushes >= 3 && ops.last() == Some(&Op::Pop)
+    };
+
+    let mut rng = Rng::new(1);
+    let mut ops: Vec<Op> = (0..100).map(|_| Op::random(&mut rng)).collect();
+    ops.extend([Op::Push(5), Op::Push(6), Op::Push(7), Op::Pop]);
+    assert!(fails(&ops));
+
+    let minimal = shrink(ops, fails);
+    assert_eq!(minimal, [Op::Push(0), Op::Push(0), Op::Push(0), Op::Pop]);
+}
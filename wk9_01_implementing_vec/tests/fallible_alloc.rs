@@ -0,0 +1,159 @@
+// integration tests get their own binary, so we can swap out the global allocator here
+// without affecting the rest of the test suite
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use wk9_01_implementing_vec::{BumpAllocator, MyVec, TryReserveErrorKind};
+
+/// Forwards to the system allocator, but can be told to fail the Nth allocation on the current thread.
+struct FailingAllocator;
+
+thread_local! {
+    // how many more allocations succeed before one fails, or `None` to never fail
+    static ALLOCS_UNTIL_FAILURE: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+impl FailingAllocator {
+    /// Makes the `n`th allocation (counting from 1) on this thread return null.
+    fn fail_on_nth(n: usize) {
+        assert!(n > 0);
+        ALLOCS_UNTIL_FAILURE.with(|count| count.set(Some(n - 1)));
+    }
+
+    fn reset() {
+        ALLOCS_UNTIL_FAILURE.with(|count| count.set(None));
+    }
+
+    fn should_fail() -> bool {
+        ALLOCS_UNTIL_FAILURE.with(|count| match count.get() {
+            None => false,
+            Some(0) => {
+                count.set(None);
+                true
+            }
+            Some(n) => {
+                count.set(Some(n - 1));
+                false
+            }
+        })
+    }
+}
+
+unsafe impl GlobalAlloc for FailingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if Self::should_fail() {
+            std::ptr::null_mut()
+        } else {
+            unsafe { System.alloc(layout) }
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if Self::should_fail() {
+            std::ptr::null_mut()
+        } else {
+            unsafe { System.realloc(ptr, layout, new_size) }
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: FailingAllocator = FailingAllocator;
+
+#[test]
+fn test_try_push_first_allocation_fails() {
+    let mut vec = MyVec::<u64>::new();
+
+    FailingAllocator::fail_on_nth(1);
+    let (value, err) = vec.try_push(1).unwrap_err();
+    FailingAllocator::reset();
+    assert_eq!(value, 1);
+
+    match err.kind() {
+        TryReserveErrorKind::AllocError { layout } => assert_eq!(layout.size(), 8 * 8),
+        kind => panic!("expected an allocation error, got {kind:?}"),
+    }
+    assert!(vec.is_empty());
+    assert_eq!(vec.capacity(), 0);
+
+    // the allocator works again, so we can carry on as normal
+    vec.try_push(2).unwrap();
+    assert_eq!(vec[..], [2]);
+}
+
+#[test]
+fn test_failed_realloc_keeps_contents() {
+    let mut vec = MyVec::new();
+    for i in 0..8 {
+        vec.try_push(i.to_string()).unwrap();
+    }
+
+    // the 9th push has to grow the buffer, which is the next allocation
+    let ninth = "8".to_string();
+    FailingAllocator::fail_on_nth(1);
+    let (ninth, _) = vec.try_push(ninth).unwrap_err();
+    FailingAllocator::reset();
+    assert_eq!(ninth, "8");
+
+    assert_eq!(vec.len(), 8);
+    assert_eq!(vec.capacity(), 8);
+    assert!(vec.iter().enumerate().all(|(i, s)| *s == i.to_string()));
+}
+
+#[test]
+fn test_try_reserve_fails_on_nth() {
+    let mut vec = MyVec::<u32>::try_with_capacity(4).unwrap();
+
+    FailingAllocator::fail_on_nth(3);
+    assert!(vec.try_reserve_exact(8).is_ok());
+    assert!(vec.try_reserve_exact(16).is_ok());
+    let err = vec.try_reserve_exact(32).unwrap_err();
+    FailingAllocator::reset();
+
+    assert!(matches!(err.kind(), TryReserveErrorKind::AllocError { .. }));
+    assert_eq!(vec.capacity(), 16);
+
+    FailingAllocator::fail_on_nth(1);
+    assert!(MyVec::<u32>::try_with_capacity(10).is_err());
+    FailingAllocator::reset();
+}
+
+#[test]
+fn test_capacity_overflow_is_not_alloc_error() {
+    let mut vec = MyVec::<u8>::new();
+    vec.try_push(0).unwrap();
+
+    let err = vec.try_reserve(usize::MAX).unwrap_err();
+    assert_eq!(err.kind(), TryReserveErrorKind::CapacityOverflow);
+
+    for capacity in [usize::MAX / 4, isize::MAX as usize / 8 + 1] {
+        let Err(err) = MyVec::<u64>::try_with_capacity(capacity) else {
+            panic!("{capacity} u64s can't fit in memory");
+        };
+        assert_eq!(err.kind(), TryReserveErrorKind::CapacityOverflow);
+    }
+
+    assert_eq!(vec[..], [0]);
+}
+
+#[test]
+fn test_splice_only_uses_the_vectors_allocator() {
+    let bump = BumpAllocator::with_capacity(1024);
+    let mut vec = MyVec::new_in(&bump);
+    vec.extend_from_slice(&[1u32, 2, 3]);
+
+    // if `splice` went to the global allocator for scratch space, this would abort
+    FailingAllocator::fail_on_nth(1);
+    // `filter` gives no lower bound, so the tail has to be moved more than once
+    vec.splice(1..2, (10..40).filter(|_| true));
+    FailingAllocator::reset();
+
+    assert_eq!(vec.len(), 32);
+    assert!(vec[1..31].iter().copied().eq(10..40));
+    assert_eq!([vec[0], vec[31]], [1, 3]);
+}
@@ -0,0 +1,141 @@
+// Most of these are checked by the compiler rather than at runtime: if `MyVec` had the wrong variance
+// or auto traits, this file would stop compiling. The negative cases (things that *shouldn't* compile)
+// are `compile_fail` doc tests on `MyVec` itself.
+
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use wk9_01_implementing_vec::{Drain, IntoIter, Iter, IterMut, MyVec, MyVecDeque};
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn test_auto_traits() {
+    assert_send::<MyVec<String>>();
+    assert_sync::<MyVec<String>>();
+    assert_send::<IntoIter<String>>();
+    assert_sync::<IntoIter<String>>();
+    assert_send::<Iter<'static, String>>();
+    assert_sync::<Iter<'static, String>>();
+    assert_send::<IterMut<'static, String>>();
+    assert_send::<Drain<'static, String>>();
+    assert_send::<MyVecDeque<String>>();
+    assert_sync::<MyVecDeque<String>>();
+
+    // `Cell` is `Send` but not `Sync`, so a vector of them can move between threads but not be shared
+    assert_send::<MyVec<Cell<i32>>>();
+    assert_send::<IntoIter<Cell<i32>>>();
+    assert_send::<MyVecDeque<Cell<i32>>>();
+}
+
+// these only compile if `MyVec` and friends are covariant in `T`
+#[allow(dead_code)]
+fn shorten_vec<'a>(vec: MyVec<&'static str>) -> MyVec<&'a str> {
+    vec
+}
+
+#[allow(dead_code)]
+fn shorten_vec_ref<'r, 'a>(vec: &'r MyVec<&'static str>) -> &'r MyVec<&'a str> {
+    vec
+}
+
+#[allow(dead_code)]
+fn shorten_into_iter<'a>(iter: IntoIter<&'static str>) -> IntoIter<&'a str> {
+    iter
+}
+
+#[allow(dead_code)]
+fn shorten_iter<'r, 'a>(iter: Iter<'r, &'static str>) -> Iter<'r, &'a str> {
+    iter
+}
+
+#[test]
+fn test_covariance() {
+    let local = String::from("local");
+    let mut vec = shorten_vec(MyVec::new());
+    vec.push("static");
+    vec.push(&local);
+    assert_eq!(vec[..], ["static", "local"]);
+}
+
+#[test]
+fn test_move_into_thread() {
+    // same as moving a `Vec` into a thread in the wk8 examples
+    let mut vec = MyVec::new();
+    for i in 0..10 {
+        vec.push(i.to_string());
+    }
+
+    let handle = thread::spawn(move || vec.iter().map(|s| s.len()).sum::<usize>());
+    assert_eq!(handle.join().unwrap(), 10);
+}
+
+#[test]
+fn test_share_between_threads() {
+    let mut vec = MyVec::new();
+    for i in 0..100 {
+        vec.push(i);
+    }
+
+    let sums = Mutex::new(MyVec::new());
+    thread::scope(|scope| {
+        for chunk in vec.chunks(25) {
+            let sums = &sums;
+            scope.spawn(move || sums.lock().unwrap().push(chunk.iter().sum::<i32>()));
+        }
+    });
+
+    assert_eq!(sums.lock().unwrap().iter().sum::<i32>(), (0..100).sum());
+
+    let shared = Arc::new(vec);
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || shared.len())
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 100);
+    }
+}
+
+#[test]
+fn test_drop_check() {
+    // references to values that outlive the vector, same as `Vec`
+    let a = String::from("a");
+    let b = String::from("b");
+    let mut vec = MyVec::new();
+    vec.push(&a);
+    vec.push(&b);
+    assert_eq!(vec.len(), 2);
+
+    // a vector of vectors of references, dropped in the right order
+    let mut nested = MyVec::new();
+    nested.push(MyVec::new());
+    nested[0].push(&a);
+    drop(nested);
+
+    // a struct holding a vector of references into something declared before it
+    struct Graph<'a> {
+        names: MyVec<&'a str>,
+    }
+    let name = String::from("node");
+    let mut graph = Graph {
+        names: MyVec::new(),
+    };
+    graph.names.push(&name);
+    assert_eq!(graph.names[0], "node");
+}
+
+// Without `#[may_dangle]` (nightly only), `MyVec`'s destructor might look at the dangling `&s`,
+// so the compiler rejects this. `Vec` accepts it, and so does `MyVec` with the `nightly` feature.
+#[cfg(feature = "nightly")]
+#[test]
+fn test_drop_check_may_dangle() {
+    let mut vec = MyVec::new();
+    let s = String::from("dropped before vec");
+    vec.push(&s);
+    assert_eq!(vec.len(), 1);
+}